@@ -0,0 +1,77 @@
+use std::time::{Duration, Instant};
+
+use ruinous::lexer::{
+    rules::{Rules, TokenRules},
+    Lexer,
+};
+
+#[derive(Debug)]
+enum Token {
+    Keyword,
+    Ident,
+    Number,
+    Symbol,
+}
+
+impl TokenRules for Token {
+    fn rules() -> Rules<Self> {
+        Rules::new()
+            .literal("let", |_| Token::Keyword)
+            .literal("fn", |_| Token::Keyword)
+            .literal("return", |_| Token::Keyword)
+            .regex("[a-zA-Z_][a-zA-Z0-9_]*", |_| Token::Ident)
+            .and_then(|rules| rules.regex(r"\d+(\.\d+)?", |_| Token::Number))
+            .and_then(|rules| rules.regex(r"[-+*/=;(){},<>]|->", |_| Token::Symbol))
+            .and_then(|rules| rules.skip(r"\s+"))
+            .unwrap()
+    }
+}
+
+fn source(lines: usize) -> String {
+    (0..lines)
+        .map(|line| {
+            format!("fn f{line}(a, b) -> {{ let x{line} = a * {line}.5 + b; return x{line}; }}\n")
+        })
+        .collect()
+}
+
+fn time<F: FnMut() -> usize>(name: &str, mut run: F) {
+    let mut best = Duration::MAX;
+    let mut tokens = 0;
+
+    for _ in 0..5 {
+        let start = Instant::now();
+        tokens = run();
+        best = best.min(start.elapsed());
+    }
+
+    println!("{name:>20}: {best:>10.2?} ({tokens} tokens)");
+}
+
+fn main() {
+    let input = source(20_000);
+
+    time("nfa, by char", || {
+        let mut count = 0;
+        Lexer::with_str(&input)
+            .lex(Token::lexer(), |_| count += 1)
+            .unwrap();
+        count
+    });
+
+    time("dfa, by char", || {
+        let mut count = 0;
+        Lexer::with_str(&input)
+            .lex(Token::dfa(), |_| count += 1)
+            .unwrap();
+        count
+    });
+
+    time("dfa, by line", || {
+        let mut count = 0;
+        Lexer::with_str(&input)
+            .lex_lines(Token::dfa(), |_| count += 1)
+            .unwrap();
+        count
+    });
+}
@@ -0,0 +1,351 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+const DUMMY: usize = usize::MAX;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Symbol {
+    Terminal(usize),
+    Nonterminal(usize),
+}
+
+pub struct Production {
+    pub lhs: usize,
+    pub rhs: Vec<Symbol>,
+}
+
+/// A grammar whose last terminal is the end of input, and whose last production is
+/// the augmented start production.
+pub struct Grammar {
+    pub terminals: usize,
+    pub nonterminals: usize,
+    pub productions: Vec<Production>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Shift(usize),
+    Reduce(usize),
+    Accept,
+}
+
+pub struct Tables {
+    pub actions: Vec<BTreeMap<usize, Action>>,
+    pub gotos: Vec<BTreeMap<usize, usize>>,
+}
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Conflict {
+    pub terminal: usize,
+    pub shifts: Vec<Item>,
+    pub reduces: Vec<usize>,
+}
+
+pub type Item = (usize, usize);
+
+type Lookaheads = BTreeMap<Item, BTreeSet<usize>>;
+
+struct First {
+    nullable: Vec<bool>,
+    first: Vec<BTreeSet<usize>>,
+}
+
+impl Grammar {
+    pub fn build(&self) -> Result<Tables, Vec<Conflict>> {
+        let first = self.first();
+        let (kernels, transitions) = self.states();
+        let lookaheads = self.lookaheads(&first, &kernels, &transitions);
+
+        let mut tables = Tables {
+            actions: Vec::new(),
+            gotos: Vec::new(),
+        };
+
+        let mut conflicts = BTreeSet::new();
+
+        for (state, lookaheads) in lookaheads.into_iter().enumerate() {
+            let mut shifts = BTreeMap::<usize, Vec<Item>>::new();
+            let mut reduces = BTreeMap::<usize, BTreeSet<usize>>::new();
+
+            for (item, terminals) in self.closure(&first, lookaheads) {
+                match self.next(item) {
+                    Some(Symbol::Terminal(terminal)) => {
+                        shifts.entry(terminal).or_default().push(item);
+                    }
+                    Some(Symbol::Nonterminal(_)) => (),
+                    None => {
+                        for terminal in terminals {
+                            reduces.entry(terminal).or_default().insert(item.0);
+                        }
+                    }
+                }
+            }
+
+            let mut actions = BTreeMap::new();
+
+            for (&terminal, items) in &shifts {
+                let target = transitions[state][&Symbol::Terminal(terminal)];
+                actions.insert(terminal, Action::Shift(target));
+
+                if let Some(reduces) = reduces.get(&terminal) {
+                    conflicts.insert(Conflict {
+                        terminal,
+                        shifts: items.clone(),
+                        reduces: reduces.iter().copied().collect(),
+                    });
+                }
+            }
+
+            for (terminal, productions) in reduces {
+                if productions.len() > 1 && !shifts.contains_key(&terminal) {
+                    conflicts.insert(Conflict {
+                        terminal,
+                        shifts: Vec::new(),
+                        reduces: productions.iter().copied().collect(),
+                    });
+                }
+
+                let production = *productions.first().expect("reductions are never empty");
+
+                actions
+                    .entry(terminal)
+                    .or_insert(if production == self.start() {
+                        Action::Accept
+                    } else {
+                        Action::Reduce(production)
+                    });
+            }
+
+            tables.actions.push(actions);
+            tables.gotos.push(
+                transitions[state]
+                    .iter()
+                    .filter_map(|(symbol, &target)| match symbol {
+                        Symbol::Nonterminal(nonterminal) => Some((*nonterminal, target)),
+                        Symbol::Terminal(_) => None,
+                    })
+                    .collect(),
+            );
+        }
+
+        if conflicts.is_empty() {
+            Ok(tables)
+        } else {
+            Err(conflicts.into_iter().collect())
+        }
+    }
+
+    fn start(&self) -> usize {
+        self.productions.len() - 1
+    }
+
+    fn next(&self, (production, dot): Item) -> Option<Symbol> {
+        self.productions[production].rhs.get(dot).copied()
+    }
+
+    fn first(&self) -> First {
+        let mut first = First {
+            nullable: vec![false; self.nonterminals],
+            first: vec![BTreeSet::new(); self.nonterminals],
+        };
+
+        let mut changed = true;
+
+        while changed {
+            changed = false;
+
+            for production in &self.productions {
+                let (terminals, nullable) = first.sequence(&production.rhs);
+                let lhs = production.lhs;
+
+                if nullable && !first.nullable[lhs] {
+                    first.nullable[lhs] = true;
+                    changed = true;
+                }
+
+                for terminal in terminals {
+                    changed |= first.first[lhs].insert(terminal);
+                }
+            }
+        }
+
+        first
+    }
+
+    fn states(&self) -> (Vec<Vec<Item>>, Vec<BTreeMap<Symbol, usize>>) {
+        let mut kernels = vec![vec![(self.start(), 0)]];
+        let mut index = HashMap::from([(kernels[0].clone(), 0)]);
+        let mut transitions = Vec::new();
+
+        while transitions.len() < kernels.len() {
+            let mut closure = kernels[transitions.len()]
+                .iter()
+                .map(|&item| (item, BTreeSet::new()))
+                .collect::<Lookaheads>();
+
+            let mut pending = closure.keys().copied().collect::<Vec<_>>();
+
+            while let Some(item) = pending.pop() {
+                if let Some(Symbol::Nonterminal(nonterminal)) = self.next(item) {
+                    for production in self.productions_of(nonterminal) {
+                        if closure.insert((production, 0), BTreeSet::new()).is_none() {
+                            pending.push((production, 0));
+                        }
+                    }
+                }
+            }
+
+            let mut targets = BTreeMap::<Symbol, Vec<Item>>::new();
+
+            for item in closure.into_keys() {
+                if let Some(symbol) = self.next(item) {
+                    targets
+                        .entry(symbol)
+                        .or_default()
+                        .push((item.0, item.1 + 1));
+                }
+            }
+
+            let targets = targets
+                .into_iter()
+                .map(|(symbol, kernel)| {
+                    let target = *index.entry(kernel.clone()).or_insert_with(|| {
+                        kernels.push(kernel);
+                        kernels.len() - 1
+                    });
+
+                    (symbol, target)
+                })
+                .collect();
+
+            transitions.push(targets);
+        }
+
+        (kernels, transitions)
+    }
+
+    fn lookaheads(
+        &self,
+        first: &First,
+        kernels: &[Vec<Item>],
+        transitions: &[BTreeMap<Symbol, usize>],
+    ) -> Vec<Lookaheads> {
+        let mut lookaheads = kernels
+            .iter()
+            .map(|kernel| kernel.iter().map(|&item| (item, BTreeSet::new())).collect())
+            .collect::<Vec<Lookaheads>>();
+
+        let mut propagate = Vec::new();
+
+        for (state, kernel) in kernels.iter().enumerate() {
+            for &from in kernel {
+                let closure =
+                    self.closure(first, BTreeMap::from([(from, BTreeSet::from([DUMMY]))]));
+
+                for (item, terminals) in closure {
+                    let Some(symbol) = self.next(item) else {
+                        continue;
+                    };
+
+                    let target = transitions[state][&symbol];
+                    let to = (item.0, item.1 + 1);
+
+                    for terminal in terminals {
+                        if terminal == DUMMY {
+                            propagate.push(((state, from), (target, to)));
+                        } else {
+                            lookaheads[target].entry(to).or_default().insert(terminal);
+                        }
+                    }
+                }
+            }
+        }
+
+        lookaheads[0]
+            .entry((self.start(), 0))
+            .or_default()
+            .insert(self.terminals - 1);
+
+        let mut changed = true;
+
+        while changed {
+            changed = false;
+
+            for &((state, from), (target, to)) in &propagate {
+                let terminals = lookaheads[state][&from].clone();
+                let entry = lookaheads[target].entry(to).or_default();
+
+                for terminal in terminals {
+                    changed |= entry.insert(terminal);
+                }
+            }
+        }
+
+        lookaheads
+    }
+
+    fn closure(&self, first: &First, mut items: Lookaheads) -> Lookaheads {
+        let mut pending = items.keys().copied().collect::<Vec<_>>();
+
+        while let Some(item) = pending.pop() {
+            let Some(Symbol::Nonterminal(nonterminal)) = self.next(item) else {
+                continue;
+            };
+
+            let rest = &self.productions[item.0].rhs[item.1 + 1..];
+            let (mut terminals, nullable) = first.sequence(rest);
+
+            if nullable {
+                terminals.extend(items[&item].iter().copied());
+            }
+
+            for production in self.productions_of(nonterminal) {
+                let added = match items.get_mut(&(production, 0)) {
+                    Some(entry) => {
+                        let before = entry.len();
+                        entry.extend(terminals.iter().copied());
+                        entry.len() > before
+                    }
+                    None => items.insert((production, 0), terminals.clone()).is_none(),
+                };
+
+                if added {
+                    pending.push((production, 0));
+                }
+            }
+        }
+
+        items
+    }
+
+    fn productions_of(&self, nonterminal: usize) -> impl Iterator<Item = usize> + '_ {
+        self.productions
+            .iter()
+            .enumerate()
+            .filter(move |(_, production)| production.lhs == nonterminal)
+            .map(|(index, _)| index)
+    }
+}
+
+impl First {
+    fn sequence(&self, symbols: &[Symbol]) -> (BTreeSet<usize>, bool) {
+        let mut terminals = BTreeSet::new();
+
+        for symbol in symbols {
+            match *symbol {
+                Symbol::Terminal(terminal) => {
+                    terminals.insert(terminal);
+                    return (terminals, false);
+                }
+                Symbol::Nonterminal(nonterminal) => {
+                    terminals.extend(self.first[nonterminal].iter().copied());
+
+                    if !self.nullable[nonterminal] {
+                        return (terminals, false);
+                    }
+                }
+            }
+        }
+
+        (terminals, true)
+    }
+}
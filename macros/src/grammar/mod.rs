@@ -0,0 +1,592 @@
+use std::collections::HashMap;
+
+use proc_macro2::{Span, TokenStream};
+use quote::{format_ident, quote};
+use syn::{
+    braced, parenthesized,
+    parse::{Parse, ParseStream},
+    Expr, Ident, Pat, Token, Type, Visibility,
+};
+
+use self::lalr::{Action, Conflict, Production, Symbol};
+
+mod lalr;
+
+pub struct Grammar {
+    vis: Visibility,
+    name: Ident,
+    token: Type,
+    terminals: Vec<(Ident, Pat)>,
+    sync: Vec<Ident>,
+    rules: Vec<Rule>,
+}
+
+struct Rule {
+    name: Ident,
+    ty: Type,
+    alternatives: Vec<Alternative>,
+}
+
+struct Alternative {
+    symbols: Vec<(Option<Ident>, Ident)>,
+    action: Expr,
+    span: Span,
+}
+
+pub fn expand(grammar: &Grammar) -> syn::Result<TokenStream> {
+    let symbols = grammar.symbols()?;
+
+    let mut productions = Vec::new();
+    let mut alternatives = Vec::new();
+
+    for (lhs, rule) in grammar.rules.iter().enumerate() {
+        if rule.alternatives.is_empty() {
+            return Err(syn::Error::new(
+                rule.name.span(),
+                format!("rule `{}` has no alternatives", rule.name),
+            ));
+        }
+
+        for alternative in &rule.alternatives {
+            let rhs = alternative
+                .symbols
+                .iter()
+                .map(|(_, symbol)| {
+                    symbols.get(&symbol.to_string()).copied().ok_or_else(|| {
+                        syn::Error::new(symbol.span(), format!("unknown symbol `{symbol}`"))
+                    })
+                })
+                .collect::<syn::Result<_>>()?;
+
+            productions.push(Production { lhs, rhs });
+            alternatives.push((rule, alternative));
+        }
+    }
+
+    productions.push(Production {
+        lhs: grammar.rules.len(),
+        rhs: vec![Symbol::Nonterminal(0)],
+    });
+
+    let lalr = lalr::Grammar {
+        terminals: grammar.terminals.len() + 2,
+        nonterminals: grammar.rules.len() + 1,
+        productions,
+    };
+
+    let tables = lalr.build().map_err(|conflicts| {
+        conflicts
+            .iter()
+            .map(|conflict| grammar.conflict(&lalr, &alternatives, conflict))
+            .reduce(|mut error, next| {
+                error.combine(next);
+                error
+            })
+            .expect("there is at least one conflict")
+    })?;
+
+    let Grammar {
+        vis, name, token, ..
+    } = grammar;
+
+    let value = format_ident!("__{}Value", name);
+    let variants = (0..grammar.rules.len()).map(|index| format_ident!("N{}", index));
+    let types = grammar.rules.iter().map(|rule| &rule.ty);
+    let start = &grammar.rules[0].ty;
+
+    let sync = grammar
+        .sync
+        .iter()
+        .map(|name| match symbols.get(&name.to_string()) {
+            Some(Symbol::Terminal(terminal)) if *terminal != grammar.terminals.len() => {
+                Ok(*terminal)
+            }
+            _ => Err(syn::Error::new(
+                name.span(),
+                format!("`{name}` is not a terminal"),
+            )),
+        })
+        .collect::<syn::Result<Vec<_>>>()?;
+
+    let patterns = grammar.terminals.iter().map(|(_, pattern)| pattern);
+    let indices = 0..grammar.terminals.len();
+
+    let names = grammar
+        .terminals
+        .iter()
+        .map(|(name, _)| name.to_string())
+        .chain(["error".to_owned(), "end of input".to_owned()]);
+
+    let actions = tables.actions.iter().map(|actions| {
+        let actions = actions.iter().map(|(terminal, action)| {
+            let action = match action {
+                Action::Shift(state) => quote! { Shift(#state) },
+                Action::Reduce(production) => quote! { Reduce(#production) },
+                Action::Accept => quote! { Accept },
+            };
+
+            quote! { (#terminal, ::ruinous::parser::lalr::Action::#action) }
+        });
+
+        quote! { &[#(#actions),*] }
+    });
+
+    let gotos = tables.gotos.iter().map(|gotos| {
+        let gotos = gotos
+            .iter()
+            .map(|(nonterminal, state)| quote! { (#nonterminal, #state) });
+
+        quote! { &[#(#gotos),*] }
+    });
+
+    let lengths = lalr.productions.iter().map(|production| {
+        let (lhs, length) = (production.lhs, production.rhs.len());
+        quote! { (#lhs, #length) }
+    });
+
+    let reductions = alternatives
+        .iter()
+        .enumerate()
+        .map(|(index, (rule, alternative))| {
+            let bindings = alternative.symbols.iter().map(|(binding, symbol)| {
+                let extract = match symbols[&symbol.to_string()] {
+                    Symbol::Terminal(terminal) if terminal == grammar.terminals.len() => quote! {
+                        match __values.next() {
+                            ::std::option::Option::Some(::ruinous::span::Span {
+                                start: __start,
+                                end: __end,
+                                data: #value::Error,
+                            }) => ::ruinous::span::Span::new(__start, __end, ()),
+                            _ => ::std::unreachable!(),
+                        }
+                    },
+                    Symbol::Terminal(_) => quote! {
+                        match __values.next() {
+                            ::std::option::Option::Some(::ruinous::span::Span {
+                                start: __start,
+                                end: __end,
+                                data: #value::Token(__token),
+                            }) => ::ruinous::span::Span::new(__start, __end, __token),
+                            _ => ::std::unreachable!(),
+                        }
+                    },
+                    Symbol::Nonterminal(nonterminal) => {
+                        let variant = format_ident!("N{}", nonterminal);
+
+                        quote! {
+                            match __values.next() {
+                                ::std::option::Option::Some(::ruinous::span::Span {
+                                    data: #value::#variant(__value),
+                                    ..
+                                }) => __value,
+                                _ => ::std::unreachable!(),
+                            }
+                        }
+                    }
+                };
+
+                match binding {
+                    Some(binding) => quote! { let #binding = #extract; },
+                    None => quote! { #extract; },
+                }
+            });
+
+            let lhs = symbols[&rule.name.to_string()];
+            let Symbol::Nonterminal(lhs) = lhs else {
+                unreachable!("rules are nonterminals");
+            };
+
+            let variant = format_ident!("N{}", lhs);
+            let ty = &rule.ty;
+            let action = &alternative.action;
+
+            quote! {
+                #index => {
+                    let mut __values = __values.into_iter();
+                    #(#bindings)*
+                    let __value: #ty = #action;
+                    #value::#variant(__value)
+                }
+            }
+        });
+
+    Ok(quote! {
+        #vis struct #name {
+            driver: ::ruinous::parser::lalr::Driver<#token, #value>,
+        }
+
+        #[doc(hidden)]
+        #[allow(non_camel_case_types)]
+        enum #value {
+            Token(#token),
+            Error,
+            #(#variants(#types),)*
+        }
+
+        impl ::std::convert::From<#token> for #value {
+            fn from(token: #token) -> Self {
+                Self::Token(token)
+            }
+        }
+
+        impl ::std::default::Default for #value {
+            fn default() -> Self {
+                Self::Error
+            }
+        }
+
+        impl #name {
+            #vis fn new() -> Self {
+                fn terminal(token: &#token) -> ::std::option::Option<usize> {
+                    #[allow(unreachable_patterns)]
+                    match token {
+                        #(#patterns => ::std::option::Option::Some(#indices),)*
+                        _ => ::std::option::Option::None,
+                    }
+                }
+
+                #[allow(unused_variables, clippy::let_unit_value)]
+                fn reduce(
+                    production: usize,
+                    __values: ::std::vec::Vec<::ruinous::span::Span<#value>>,
+                    span: ::ruinous::span::Span<()>,
+                ) -> #value {
+                    match production {
+                        #(#reductions)*
+                        _ => ::std::unreachable!(),
+                    }
+                }
+
+                static TABLES: ::ruinous::parser::lalr::Tables<#token, #value> =
+                    ::ruinous::parser::lalr::Tables {
+                        terminals: &[#(#names),*],
+                        actions: &[#(#actions),*],
+                        gotos: &[#(#gotos),*],
+                        productions: &[#(#lengths),*],
+                        sync: &[#(#sync),*],
+                        terminal,
+                        reduce,
+                    };
+
+                Self {
+                    driver: ::ruinous::parser::lalr::Driver::new(&TABLES),
+                }
+            }
+
+            #vis fn finish_partial(
+                self,
+            ) -> (
+                ::std::option::Option<#start>,
+                ::std::vec::Vec<::ruinous::parser::SyntaxError>,
+            ) {
+                match self.driver.finish_partial() {
+                    (::std::option::Option::Some(#value::N0(value)), errors) => {
+                        (::std::option::Option::Some(value), errors)
+                    }
+                    (_, errors) => (::std::option::Option::None, errors),
+                }
+            }
+        }
+
+        impl ::std::default::Default for #name {
+            fn default() -> Self {
+                Self::new()
+            }
+        }
+
+        impl ::ruinous::parser::state::State<#token> for #name {
+            type Ast = #start;
+            type Error = ::ruinous::parser::SyntaxError;
+
+            fn process(&mut self, token: ::ruinous::span::Span<#token>) {
+                self.driver.process(token);
+            }
+
+            fn finish(
+                self,
+            ) -> ::std::result::Result<#start, ::ruinous::parser::ParseErrors<Self::Error>> {
+                match self.driver.finish()? {
+                    #value::N0(value) => ::std::result::Result::Ok(value),
+                    _ => ::std::unreachable!(),
+                }
+            }
+        }
+    })
+}
+
+impl Grammar {
+    fn symbols(&self) -> syn::Result<HashMap<String, Symbol>> {
+        let mut symbols =
+            HashMap::from([("error".to_owned(), Symbol::Terminal(self.terminals.len()))]);
+
+        let terminals = self
+            .terminals
+            .iter()
+            .enumerate()
+            .map(|(index, (name, _))| (name, Symbol::Terminal(index)));
+
+        let rules = self
+            .rules
+            .iter()
+            .enumerate()
+            .map(|(index, rule)| (&rule.name, Symbol::Nonterminal(index)));
+
+        for (name, symbol) in terminals.chain(rules) {
+            if name == "error" {
+                return Err(syn::Error::new(
+                    name.span(),
+                    "`error` is reserved for error recovery",
+                ));
+            }
+
+            if symbols.insert(name.to_string(), symbol).is_some() {
+                return Err(syn::Error::new(
+                    name.span(),
+                    format!("`{name}` is defined more than once"),
+                ));
+            }
+        }
+
+        Ok(symbols)
+    }
+
+    fn conflict(
+        &self,
+        lalr: &lalr::Grammar,
+        alternatives: &[(&Rule, &Alternative)],
+        conflict: &Conflict,
+    ) -> syn::Error {
+        let item = |(production, dot): (usize, usize)| {
+            let Some((rule, alternative)) = alternatives.get(production) else {
+                return format!("(start) -> {} .", self.rules[0].name);
+            };
+
+            let mut symbols = alternative
+                .symbols
+                .iter()
+                .map(|(_, symbol)| symbol.to_string())
+                .collect::<Vec<_>>();
+
+            symbols.insert(dot, ".".to_owned());
+            format!("{} -> {}", rule.name, symbols.join(" "))
+        };
+
+        let terminal = match self.terminals.get(conflict.terminal) {
+            Some((name, _)) => format!("`{name}`"),
+            None if conflict.terminal == self.terminals.len() => "`error`".to_owned(),
+            None => "end of input".to_owned(),
+        };
+
+        let kind = if conflict.shifts.is_empty() {
+            "reduce/reduce"
+        } else {
+            "shift/reduce"
+        };
+
+        let mut message = format!("{kind} conflict on {terminal}");
+
+        for &shift in &conflict.shifts {
+            message += &format!("\n  shift:  {}", item(shift));
+        }
+
+        for &reduce in &conflict.reduces {
+            let length = lalr.productions[reduce].rhs.len();
+            message += &format!("\n  reduce: {}", item((reduce, length)));
+        }
+
+        let span = conflict
+            .reduces
+            .first()
+            .and_then(|&reduce| alternatives.get(reduce))
+            .map_or_else(Span::call_site, |(_, alternative)| alternative.span);
+
+        syn::Error::new(span, message)
+    }
+}
+
+impl Parse for Grammar {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let vis = input.parse()?;
+
+        let keyword = input.parse::<Ident>()?;
+        if keyword != "parser" {
+            return Err(syn::Error::new(keyword.span(), "expected `parser`"));
+        }
+
+        let name = input.parse()?;
+
+        let content;
+        parenthesized!(content in input);
+        let token = content.parse()?;
+
+        input.parse::<Token![;]>()?;
+
+        let mut terminals = Vec::new();
+        let mut sync = Vec::new();
+        let mut rules = Vec::new();
+
+        while !input.is_empty() {
+            let section = input.parse::<Ident>()?;
+
+            let content;
+            braced!(content in input);
+
+            if section == "terminals" {
+                while !content.is_empty() {
+                    let name = content.parse()?;
+                    content.parse::<Token![=]>()?;
+                    terminals.push((name, Pat::parse_multi_with_leading_vert(&content)?));
+
+                    if !content.is_empty() {
+                        content.parse::<Token![,]>()?;
+                    }
+                }
+            } else if section == "sync" {
+                sync.extend(content.parse_terminated(Ident::parse, Token![,])?);
+            } else if section == "rules" {
+                while !content.is_empty() {
+                    rules.push(content.parse()?);
+                }
+            } else {
+                return Err(syn::Error::new(
+                    section.span(),
+                    "expected `terminals`, `sync` or `rules`",
+                ));
+            }
+        }
+
+        if rules.is_empty() {
+            return Err(input.error("a grammar needs at least one rule"));
+        }
+
+        Ok(Self {
+            vis,
+            name,
+            token,
+            terminals,
+            sync,
+            rules,
+        })
+    }
+}
+
+impl Parse for Rule {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let name = input.parse()?;
+        input.parse::<Token![:]>()?;
+        let ty = input.parse()?;
+
+        let content;
+        braced!(content in input);
+
+        let mut alternatives = Vec::new();
+
+        while !content.is_empty() {
+            alternatives.push(content.parse()?);
+        }
+
+        Ok(Self {
+            name,
+            ty,
+            alternatives,
+        })
+    }
+}
+
+impl Parse for Alternative {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let span = input.span();
+        let mut symbols = Vec::new();
+
+        while !input.peek(Token![=>]) {
+            let first = input.parse::<Ident>()?;
+
+            if input.peek(Token![:]) {
+                input.parse::<Token![:]>()?;
+                symbols.push((Some(first), input.parse()?));
+            } else {
+                symbols.push((None, first));
+            }
+        }
+
+        input.parse::<Token![=>]>()?;
+        let action = input.parse()?;
+
+        if input.peek(Token![,]) || !(input.is_empty() || matches!(action, Expr::Block(_))) {
+            input.parse::<Token![,]>()?;
+        }
+
+        Ok(Self {
+            symbols,
+            action,
+            span,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn conflicts(input: &str) -> Vec<String> {
+        let grammar = syn::parse_str::<Grammar>(input).unwrap();
+
+        match expand(&grammar) {
+            Ok(_) => Vec::new(),
+            Err(error) => error.into_iter().map(|error| error.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn grammar_reports_conflicts() {
+        let errors = conflicts(
+            "parser Ambiguous(Token);
+            terminals { Plus = Token::Plus, Number = Token::Number }
+            rules {
+                expr: () {
+                    expr Plus expr => (),
+                    Number => (),
+                }
+            }",
+        );
+
+        assert_eq!(
+            errors,
+            ["shift/reduce conflict on `Plus`\n  \
+                shift:  expr -> expr . Plus expr\n  \
+                reduce: expr -> expr Plus expr ."]
+        );
+
+        let errors = conflicts(
+            "parser Ambiguous(Token);
+            terminals { X = Token::X }
+            rules {
+                start: () { b => (), c => () }
+                b: () { X => () }
+                c: () { X => () }
+            }",
+        );
+
+        assert_eq!(
+            errors,
+            ["reduce/reduce conflict on end of input\n  \
+                reduce: b -> X .\n  \
+                reduce: c -> X ."]
+        );
+    }
+
+    #[test]
+    fn grammar_accepts_lalr_grammars() {
+        let errors = conflicts(
+            "parser Assignment(Token);
+            terminals { Equals = Token::Equals, Star = Token::Star, Id = Token::Id }
+            rules {
+                s: () { l Equals r => (), r => () }
+                l: () { Star r => (), Id => () }
+                r: () { l => () }
+            }",
+        );
+
+        assert!(errors.is_empty(), "{errors:?}");
+    }
+}
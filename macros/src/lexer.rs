@@ -0,0 +1,285 @@
+use proc_macro2::TokenStream;
+use quote::{quote, quote_spanned};
+use ruinous_util::automaton::{dfa::Dfa, nfa::Nfa, pattern::Pattern};
+use syn::{
+    parse::ParseStream, spanned::Spanned, Attribute, Data, DeriveInput, Expr, Fields, Ident,
+    LitInt, LitStr, Token, Variant,
+};
+
+enum Kind {
+    Token,
+    Regex,
+}
+
+struct Rule {
+    kind: Kind,
+    pattern: LitStr,
+    priority: Option<LitInt>,
+    callback: Option<Expr>,
+}
+
+pub fn derive(input: &DeriveInput) -> syn::Result<TokenStream> {
+    let Data::Enum(data) = &input.data else {
+        return Err(syn::Error::new(
+            input.span(),
+            "`Lexer` can only be derived for enums",
+        ));
+    };
+
+    let mut rules = Vec::new();
+    let mut patterns = Vec::new();
+
+    for pattern in skip_patterns(&input.attrs)? {
+        patterns.push((parse_pattern(&pattern)?, 0));
+
+        rules.push(quote_spanned! { pattern.span() =>
+            let rules = rules.skip_rule(
+                ::ruinous::lexer::rules::Rule::regex(#pattern)
+                    .expect("patterns are checked when deriving `Lexer`"),
+            );
+        });
+    }
+
+    for variant in &data.variants {
+        for attr in &variant.attrs {
+            let kind = if attr.path().is_ident("token") {
+                Kind::Token
+            } else if attr.path().is_ident("regex") {
+                Kind::Regex
+            } else {
+                continue;
+            };
+
+            let rule = attr.parse_args_with(|input: ParseStream| Rule::parse(kind, input))?;
+
+            let (pattern, priority) = match rule.kind {
+                Kind::Token => (Pattern::literal(&rule.pattern.value()), 1),
+                Kind::Regex => (parse_pattern(&rule.pattern)?, 0),
+            };
+
+            let priority = match &rule.priority {
+                Some(priority) => priority.base10_parse()?,
+                None => priority,
+            };
+
+            patterns.push((pattern, priority));
+            rules.push(variant_rule(variant, &rule)?);
+        }
+    }
+
+    let name = &input.ident;
+    let (impl_generics, type_generics, where_clause) = input.generics.split_for_impl();
+
+    let tables = dfa_tables(&patterns);
+
+    // Statics can't depend on generic parameters, so only non-generic enums get their
+    // lexers cached
+    let cached = if input.generics.params.is_empty() {
+        quote! {
+            fn lexer() -> ::ruinous::lexer::rules::RuleLexer<Self> {
+                static LEXER: ::std::sync::OnceLock<::ruinous::lexer::rules::RuleLexer<#name>> =
+                    ::std::sync::OnceLock::new();
+
+                LEXER.get_or_init(|| Self::rules().build()).clone()
+            }
+
+            fn dfa() -> ::ruinous::lexer::rules::DfaLexer<Self> {
+                static DFA: ::std::sync::OnceLock<::ruinous::lexer::rules::DfaLexer<#name>> =
+                    ::std::sync::OnceLock::new();
+
+                DFA.get_or_init(|| Self::rules().build_dfa_from(#tables))
+                    .clone()
+            }
+        }
+    } else {
+        quote! {
+            fn dfa() -> ::ruinous::lexer::rules::DfaLexer<Self> {
+                Self::rules().build_dfa_from(#tables)
+            }
+        }
+    };
+
+    Ok(quote! {
+        impl #impl_generics ::ruinous::lexer::rules::TokenRules for #name #type_generics #where_clause {
+            fn rules() -> ::ruinous::lexer::rules::Rules<Self> {
+                let rules = ::ruinous::lexer::rules::Rules::new();
+                #(#rules)*
+                rules
+            }
+
+            #cached
+        }
+    })
+}
+
+fn skip_patterns(attrs: &[Attribute]) -> syn::Result<Vec<LitStr>> {
+    let mut patterns = Vec::new();
+
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("lexer")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("skip") {
+                patterns.push(meta.value()?.parse()?);
+                Ok(())
+            } else {
+                Err(meta.error("expected `skip = \"...\"`"))
+            }
+        })?;
+    }
+
+    Ok(patterns)
+}
+
+fn variant_rule(variant: &Variant, rule: &Rule) -> syn::Result<TokenStream> {
+    let ident = &variant.ident;
+    let pattern = &rule.pattern;
+
+    let constructor = match (&variant.fields, &rule.callback) {
+        (Fields::Unit, None) => quote! { |_: &str| Self::#ident },
+        (Fields::Unnamed(fields), callback) if fields.unnamed.len() == 1 => {
+            let ty = &fields.unnamed[0].ty;
+
+            match callback {
+                Some(callback) => quote! {
+                    {
+                        let callback: fn(&str) -> #ty = #callback;
+                        move |text: &str| Self::#ident(callback(text))
+                    }
+                },
+                None => quote! {
+                    |text: &str| Self::#ident(::std::convert::From::from(text))
+                },
+            }
+        }
+        (Fields::Unit, Some(callback)) => {
+            return Err(syn::Error::new(
+                callback.span(),
+                "unit variants cannot take a callback",
+            ))
+        }
+        _ => {
+            return Err(syn::Error::new(
+                variant.span(),
+                "`Lexer` variants must be unit variants or have a single unnamed field",
+            ))
+        }
+    };
+
+    let rule_expr = match rule.kind {
+        Kind::Token => quote! { ::ruinous::lexer::rules::Rule::literal(#pattern) },
+        Kind::Regex => {
+            quote! {
+                ::ruinous::lexer::rules::Rule::regex(#pattern)
+                    .expect("patterns are checked when deriving `Lexer`")
+            }
+        }
+    };
+
+    let rule_expr = match &rule.priority {
+        Some(priority) => quote! { #rule_expr.priority(#priority) },
+        None => rule_expr,
+    };
+
+    Ok(quote_spanned! { pattern.span() =>
+        let rules = rules.rule(#rule_expr, #constructor);
+    })
+}
+
+fn parse_pattern(pattern: &LitStr) -> syn::Result<Pattern> {
+    Pattern::parse(&pattern.value()).map_err(|error| syn::Error::new(pattern.span(), error))
+}
+
+fn dfa_tables(patterns: &[(Pattern, i32)]) -> TokenStream {
+    let nfa = Nfa::new(
+        patterns
+            .iter()
+            .map(|(pattern, priority)| (pattern, *priority)),
+    );
+
+    let dfa = Dfa::new(&nfa);
+    let tables = dfa.tables();
+
+    let ascii = tables.ascii;
+    let ranges = tables
+        .ranges
+        .iter()
+        .map(|(char, class)| quote! { (#char, #class) });
+    let classes = tables.classes;
+    let transitions = tables.transitions;
+    let start = tables.start;
+
+    let accepts = tables.accepts.iter().map(|accept| match accept {
+        Some(rule) => quote! { ::std::option::Option::Some(#rule) },
+        None => quote! { ::std::option::Option::None },
+    });
+
+    quote! {
+        ::ruinous::lexer::rules::DfaTables {
+            ascii: [#(#ascii),*],
+            ranges: &[#(#ranges),*],
+            classes: #classes,
+            transitions: &[#(#transitions),*],
+            accepts: &[#(#accepts),*],
+            start: #start,
+        }
+    }
+}
+
+impl Rule {
+    fn parse(kind: Kind, input: ParseStream) -> syn::Result<Self> {
+        let pattern = input.parse()?;
+        let mut priority = None;
+        let mut callback = None;
+
+        while !input.is_empty() {
+            input.parse::<Token![,]>()?;
+
+            if input.is_empty() {
+                break;
+            }
+
+            let name = input.parse::<Ident>()?;
+            input.parse::<Token![=]>()?;
+
+            if name == "priority" {
+                priority = Some(input.parse()?);
+            } else if name == "callback" {
+                callback = Some(input.parse()?);
+            } else {
+                return Err(syn::Error::new(
+                    name.span(),
+                    "expected `priority` or `callback`",
+                ));
+            }
+        }
+
+        Ok(Self {
+            kind,
+            pattern,
+            priority,
+            callback,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn error(input: &str) -> String {
+        let input = syn::parse_str::<DeriveInput>(input).unwrap();
+        derive(&input).err().unwrap().to_string()
+    }
+
+    #[test]
+    fn derive_rejects_invalid_patterns() {
+        assert_eq!(
+            error(r#"enum Token { #[regex("a(b")] A }"#),
+            "Invalid pattern `a(b` at offset 1: unclosed group"
+        );
+
+        assert_eq!(
+            error(r#"#[lexer(skip = "[z-a]")] enum Token { #[token("a")] A }"#),
+            "Invalid pattern `[z-a]` at offset 1: invalid character range"
+        );
+    }
+}
@@ -0,0 +1,78 @@
+use proc_macro::TokenStream;
+use syn::{parse_macro_input, DeriveInput};
+
+use self::grammar::Grammar;
+
+mod grammar;
+mod lexer;
+
+#[proc_macro_derive(Lexer, attributes(lexer, token, regex))]
+pub fn derive_lexer(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    lexer::derive(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Generate an LALR(1) parser from a grammar, reporting any conflicts in it as
+/// compile errors.
+///
+/// ```ignore
+/// grammar! {
+///     pub parser Calculator(Token);
+///
+///     terminals {
+///         Plus = Token::Plus,
+///         Comma = Token::Comma,
+///         Number = Token::Number(_),
+///     }
+///
+///     sync { Comma }
+///
+///     rules {
+///         list: Vec<Span<Expr>> {
+///             => Vec::new(),
+///             l:list e:expr Comma => { let mut l = l; l.push(e); l },
+///         }
+///
+///         expr: Span<Expr> {
+///             l:expr Plus r:Number => span.swap(Expr::add(l, r)),
+///             n:Number => n.map(Expr::number),
+///         }
+///     }
+/// }
+/// ```
+///
+/// The header names the generated parser and the token type it parses. The parser is
+/// created with `new()`, and implements `ruinous::parser::state::State` for that token
+/// type.
+///
+/// - `terminals` names the tokens the rules can use, each matched by a pattern.
+/// - `sync` is optional and lists terminals to recover at after a syntax error.
+///   Tokens are skipped until one of them, and parsing resumes from there.
+/// - `rules` defines the nonterminals, each with the type it produces. The first
+///   rule is the start rule, and its type is the parser's output.
+///
+/// Each alternative is a sequence of symbols, then `=>` and an expression that
+/// produces the rule's type. A symbol can be bound with `name:symbol`. A terminal is
+/// bound as a `Span` of its token, and a nonterminal as its rule's type. An
+/// alternative with no symbols matches empty input.
+///
+/// The action can also use `span`, a `Span<()>` over everything the alternative
+/// matched. For an empty alternative, it is an empty span where the next token
+/// starts.
+///
+/// `error` is a reserved terminal that stands in for input that failed to parse, and
+/// is bound as a `Span<()>` over it. After a syntax error, the parser pops back to
+/// where an `error` can go, and skips tokens until one that can follow it. Anything
+/// that can't be recovered from this way falls back to `sync`. `finish_partial()`
+/// returns the output with the error nodes the actions built, along with the errors.
+#[proc_macro]
+pub fn grammar(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as Grammar);
+
+    grammar::expand(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
@@ -0,0 +1,234 @@
+use ruinous::{
+    lexer::{rules::TokenRules, Lexer},
+    parser::{grammar, state::State, Error, Parser, SyntaxError},
+    span::Span,
+};
+
+#[derive(Lexer, Debug, Clone, PartialEq)]
+#[lexer(skip = r"\s+")]
+enum Token {
+    #[token("+")]
+    Plus,
+
+    #[token("*")]
+    Times,
+
+    #[token("(")]
+    Open,
+
+    #[token(")")]
+    Close,
+
+    #[token(",")]
+    Comma,
+
+    #[regex("[0-9]+", callback = |text| text.parse().unwrap())]
+    Number(i64),
+}
+
+#[derive(Debug, PartialEq)]
+enum Expr {
+    Number(i64),
+    Add(Box<Span<Expr>>, Box<Span<Expr>>),
+    Mul(Box<Span<Expr>>, Box<Span<Expr>>),
+    Error,
+}
+
+grammar! {
+    pub parser Calculator(Token);
+
+    terminals {
+        Plus = Token::Plus,
+        Times = Token::Times,
+        Open = Token::Open,
+        Close = Token::Close,
+        Comma = Token::Comma,
+        Number = Token::Number(_),
+    }
+
+    sync { Comma }
+
+    rules {
+        list: Vec<Span<Expr>> {
+            => Vec::new(),
+            items:items => items,
+        }
+
+        items: Vec<Span<Expr>> {
+            e:expr => vec![e],
+            items:items Comma e:expr => {
+                let mut items = items;
+                items.push(e);
+                items
+            }
+        }
+
+        expr: Span<Expr> {
+            l:expr Plus r:term => span.swap(Expr::Add(Box::new(l), Box::new(r))),
+            t:term => t,
+        }
+
+        term: Span<Expr> {
+            l:term Times r:factor => span.swap(Expr::Mul(Box::new(l), Box::new(r))),
+            f:factor => f,
+        }
+
+        factor: Span<Expr> {
+            Open e:expr Close => e,
+            n:Number => n.map(|token| match token {
+                Token::Number(number) => Expr::Number(number),
+                _ => unreachable!(),
+            }),
+        }
+    }
+}
+
+grammar! {
+    parser Recovering(Token);
+
+    terminals {
+        Plus = Token::Plus,
+        Open = Token::Open,
+        Close = Token::Close,
+        Comma = Token::Comma,
+        Number = Token::Number(_),
+    }
+
+    rules {
+        list: Vec<Span<Expr>> {
+            i:item => vec![i],
+            l:list Comma i:item => {
+                let mut l = l;
+                l.push(i);
+                l
+            }
+        }
+
+        item: Span<Expr> {
+            e:expr => e,
+            e:error => e.swap(Expr::Error),
+        }
+
+        expr: Span<Expr> {
+            l:expr Plus r:atom => span.swap(Expr::Add(Box::new(l), Box::new(r))),
+            a:atom => a,
+        }
+
+        atom: Span<Expr> {
+            Open e:expr Close => e,
+            Open e:error Close => e.swap(Expr::Error),
+            n:Number => n.map(|token| match token {
+                Token::Number(number) => Expr::Number(number),
+                _ => unreachable!(),
+            }),
+        }
+    }
+}
+
+fn parse(input: &str) -> Result<Vec<Span<Expr>>, Vec<SyntaxError>> {
+    match Parser::with_str(input).parse(Token::lexer(), Calculator::new()) {
+        Ok(ast) => Ok(ast),
+        Err(Error::ParseErrors(errors)) => Err(errors.errors),
+        Err(error) => panic!("{error:?}"),
+    }
+}
+
+fn eval(expr: &Expr) -> i64 {
+    match expr {
+        Expr::Number(number) => *number,
+        Expr::Add(lhs, rhs) => eval(&lhs.data) + eval(&rhs.data),
+        Expr::Mul(lhs, rhs) => eval(&lhs.data) * eval(&rhs.data),
+        Expr::Error => unreachable!(),
+    }
+}
+
+fn recovered(input: &str) -> (Vec<String>, Vec<SyntaxError>) {
+    let mut parser = Recovering::new();
+
+    for token in Lexer::with_str(input).tokens(Token::lexer()) {
+        parser.process(token.unwrap());
+    }
+
+    fn show(expr: &Span<Expr>) -> String {
+        match &expr.data {
+            Expr::Number(number) => number.to_string(),
+            Expr::Add(lhs, rhs) => format!("(+ {} {})", show(lhs), show(rhs)),
+            Expr::Mul(lhs, rhs) => format!("(* {} {})", show(lhs), show(rhs)),
+            Expr::Error => format!("<error {:?}>", expr.range()),
+        }
+    }
+
+    let (ast, errors) = parser.finish_partial();
+    (ast.unwrap().iter().map(show).collect(), errors)
+}
+
+#[test]
+fn generated_parser() {
+    let ast = parse("1 + 2 * 3, (1 + 2) * 3").unwrap();
+
+    assert_eq!(
+        ast.iter().map(|expr| eval(&expr.data)).collect::<Vec<_>>(),
+        [7, 9]
+    );
+
+    assert_eq!(ast[0].range(), 0..9);
+    assert_eq!(ast[1].range(), 11..22);
+
+    assert!(parse("").unwrap().is_empty());
+}
+
+#[test]
+fn generated_parser_errors() {
+    let errors = parse("1 + * 2").unwrap_err();
+
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].span.range(), 4..5);
+    assert_eq!(errors[0].expected, ["`Open`", "`Number`"]);
+    assert_eq!(errors[0].found.as_deref(), Some("`Times`"));
+
+    let errors = parse("(1").unwrap_err();
+    assert_eq!(errors[0].expected, ["`Plus`", "`Close`"]);
+    assert_eq!(errors[0].found, None);
+}
+
+#[test]
+fn generated_parser_recovers_at_sync_tokens() {
+    let errors = parse("1 + * 2, 3, (4 +, 5").unwrap_err();
+
+    assert_eq!(
+        errors
+            .iter()
+            .map(|error| (error.span.range(), error.found.as_deref()))
+            .collect::<Vec<_>>(),
+        [(4..5, Some("`Times`")), (16..17, Some("`Comma`"))]
+    );
+
+    let errors = parse("1 + *, 2 +").unwrap_err();
+
+    assert_eq!(errors.len(), 2);
+    assert_eq!(errors[1].expected, ["`Open`", "`Number`"]);
+    assert_eq!(errors[1].found, None);
+}
+
+#[test]
+fn generated_parser_builds_error_nodes() {
+    let (ast, errors) = recovered("(1 +) + 2, 3 + + 4, 5");
+
+    assert_eq!(ast, ["(+ <error 1..4> 2)", "<error 11..18>", "5"]);
+
+    assert_eq!(
+        errors
+            .iter()
+            .map(|error| (error.span.range(), error.found.as_deref()))
+            .collect::<Vec<_>>(),
+        [(4..5, Some("`Close`")), (15..16, Some("`Plus`"))]
+    );
+
+    assert_eq!(errors[0].expected, ["`Open`", "`Number`"]);
+
+    let (ast, errors) = recovered("+ 1, 2 +");
+
+    assert_eq!(ast, ["<error 0..3>", "<error 5..8>"]);
+    assert_eq!(errors.len(), 2);
+    assert_eq!(errors[1].found, None);
+}
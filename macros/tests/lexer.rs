@@ -0,0 +1,71 @@
+use ruinous::lexer::{rules::TokenRules, Error, Lexer};
+
+#[derive(Lexer, Debug, Clone, PartialEq)]
+#[lexer(skip = r"\s+")]
+enum Token {
+    #[token("let")]
+    Let,
+
+    #[token("=")]
+    Equals,
+
+    #[token(";")]
+    Semicolon,
+
+    #[regex("[a-z_][a-z0-9_]*")]
+    Ident(String),
+
+    #[regex("[0-9]+", callback = |text| text.parse().unwrap())]
+    Number(u64),
+
+    #[regex("[0-9]+[a-z]+", priority = 2)]
+    #[regex("0x[0-9a-f]+")]
+    Suffixed(Box<str>),
+}
+
+fn lex(input: &str) -> Result<Vec<Token>, Error<ruinous::lexer::rules::RuleLexer<Token>>> {
+    let mut tokens = Vec::new();
+    Lexer::with_str(input).lex(Token::lexer(), |token| tokens.push(token.data))?;
+    Ok(tokens)
+}
+
+#[test]
+fn derived_lexer() {
+    assert_eq!(
+        lex("let letter = 42;\nlet x = 0xff 10px;").unwrap(),
+        [
+            Token::Let,
+            Token::Ident("letter".to_owned()),
+            Token::Equals,
+            Token::Number(42),
+            Token::Semicolon,
+            Token::Let,
+            Token::Ident("x".to_owned()),
+            Token::Equals,
+            Token::Suffixed("0xff".into()),
+            Token::Suffixed("10px".into()),
+            Token::Semicolon,
+        ]
+    );
+}
+
+#[test]
+fn derived_dfa_lexer() {
+    let input = "let letter = 42;\nlet x = 0xff 10px;";
+
+    let mut tokens = Vec::new();
+    Lexer::with_str(input)
+        .lex_lines(Token::dfa(), |token| tokens.push(token.data))
+        .unwrap();
+
+    assert_eq!(tokens, lex(input).unwrap());
+    assert_eq!(Token::dfa().states(), Token::rules().build_dfa().states());
+}
+
+#[test]
+fn derived_lexer_errors() {
+    match lex("let a = $;") {
+        Err(Error::LexErrors(errors)) => assert_eq!(errors.errors.len(), 1),
+        other => panic!("{other:?}"),
+    }
+}
@@ -0,0 +1,139 @@
+use std::path::PathBuf;
+
+use futures_util::io::{AsyncBufRead, AsyncRead, BufReader};
+
+use ruinous_util::span::{Columns, Span};
+
+use crate::reader::{AsyncCharReader, Encoding};
+
+use super::{
+    state::{LineState, State},
+    CallbackSink, Error,
+};
+
+pub struct AsyncLexer<R> {
+    reader: AsyncCharReader<R>,
+}
+
+impl<R: AsyncRead + Unpin> AsyncLexer<BufReader<R>> {
+    pub fn with_reader<N: Into<PathBuf>>(name: N, reader: R) -> Self {
+        let reader = AsyncCharReader::with_reader(name, reader);
+        Self { reader }
+    }
+}
+
+impl<R: AsyncBufRead + Unpin> AsyncLexer<R> {
+    pub fn new(reader: AsyncCharReader<R>) -> Self {
+        Self { reader }
+    }
+
+    #[must_use]
+    pub fn lossless(self) -> Self {
+        Self {
+            reader: self.reader.lossless(),
+        }
+    }
+
+    #[must_use]
+    pub fn encoding(self, encoding: Encoding) -> Self {
+        Self {
+            reader: self.reader.encoding(encoding),
+        }
+    }
+
+    #[must_use]
+    pub fn lossy(self) -> Self {
+        Self {
+            reader: self.reader.lossy(),
+        }
+    }
+
+    #[must_use]
+    pub fn columns(self, columns: Columns) -> Self {
+        Self {
+            reader: self.reader.columns(columns),
+        }
+    }
+
+    pub async fn lex<S: State, Callback: FnMut(Span<S::Token>)>(
+        self,
+        mut state: S,
+        mut callback: Callback,
+    ) -> Result<(), Error<S>> {
+        let mut sink = CallbackSink {
+            callback: &mut callback,
+            errors: Vec::new(),
+        };
+
+        self.reader
+            .read(|span| state.process(span, &mut sink))
+            .await?;
+
+        state.finish(&mut sink);
+
+        if sink.errors.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::LexErrors(sink.errors.into()))
+        }
+    }
+
+    pub async fn lex_lines<S: LineState, Callback: FnMut(Span<S::Token>)>(
+        mut self,
+        mut state: S,
+        mut callback: Callback,
+    ) -> Result<(), Error<S>> {
+        let mut sink = CallbackSink {
+            callback: &mut callback,
+            errors: Vec::new(),
+        };
+
+        while let Some(line) = self.reader.next_line().await? {
+            state.process_line(line, &mut sink);
+        }
+
+        state.finish(&mut sink);
+
+        if sink.errors.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::LexErrors(sink.errors.into()))
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use futures_executor::block_on;
+
+    use super::*;
+    use crate::lexer::{rules::Rules, Lexer};
+
+    #[test]
+    fn async_lex_matches_sync() {
+        let input = "let x = 10;\nlet y = x;\n";
+        let rules = Rules::new()
+            .regex("[a-z]+|[0-9]+|[=;]", str::to_owned)
+            .and_then(|rules| rules.skip(r"\s+"))
+            .unwrap();
+
+        let mut expected = Vec::new();
+        Lexer::with_str(input)
+            .lex(rules.build_dfa(), |token| expected.push(token))
+            .unwrap();
+
+        let rules = Rules::new()
+            .regex("[a-z]+|[0-9]+|[=;]", str::to_owned)
+            .and_then(|rules| rules.skip(r"\s+"))
+            .unwrap();
+
+        let mut tokens = Vec::new();
+        block_on(
+            AsyncLexer::with_reader("<upload>", input.as_bytes())
+                .lex_lines(rules.build_dfa(), |token| tokens.push(token)),
+        )
+        .unwrap();
+
+        assert_eq!(tokens, expected);
+    }
+}
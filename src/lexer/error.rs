@@ -8,7 +8,17 @@ use super::state::State;
 
 pub enum Error<S: State> {
     FileError(FileError),
-    LexError(S::Error),
+    LexErrors(LexErrors<S::Error>),
+}
+
+pub struct LexErrors<E: ErrorProvider> {
+    pub errors: Vec<E>,
+}
+
+impl<E: ErrorProvider> From<Vec<E>> for LexErrors<E> {
+    fn from(errors: Vec<E>) -> Self {
+        Self { errors }
+    }
 }
 
 impl<S: State> From<FileError> for Error<S> {
@@ -21,20 +31,38 @@ impl<S: State> Debug for Error<S> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Error::FileError(error) => f.debug_tuple("FileError").field(&error).finish(),
-            Error::LexError(error) => f.debug_tuple("LexError").field(&error).finish(),
+            Error::LexErrors(error) => f.debug_tuple("LexErrors").field(&error).finish(),
         }
     }
 }
 
+impl<E: ErrorProvider> Debug for LexErrors<E> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LexErrors")
+            .field("errors", &self.errors)
+            .finish()
+    }
+}
+
 impl<S: State> ErrorProvider for Error<S> {
     fn write_errors(&self, writer: &mut dyn ErrorWriter) -> std::fmt::Result {
         match self {
             Error::FileError(error) => error.write_errors(writer),
-            Error::LexError(error) => error.write_errors(writer),
+            Error::LexErrors(errors) => errors.write_errors(writer),
         }
     }
 }
 
+impl<E: ErrorProvider> ErrorProvider for LexErrors<E> {
+    fn write_errors(&self, writer: &mut dyn ErrorWriter) -> std::fmt::Result {
+        for error in &self.errors {
+            error.write_errors(writer)?;
+        }
+
+        Ok(())
+    }
+}
+
 impl ErrorProvider for FileError {
     fn write_errors(&self, writer: &mut dyn ErrorWriter) -> std::fmt::Result {
         match self {
@@ -46,6 +74,106 @@ impl ErrorProvider for FileError {
                 None,
                 &format!("Unable to read file `{}`: {}", file.display(), source),
             ),
+            FileError::InvalidEncoding {
+                span,
+                encoding,
+                bytes,
+                ..
+            } => writer.error(
+                Some(*span),
+                &format!("Invalid {encoding} sequence {bytes:02X?}"),
+            ),
+            FileError::InvalidEdit { span, length } => writer.error(
+                None,
+                &format!(
+                    "Invalid edit of {:?} in text of length {length}",
+                    span.range()
+                ),
+            ),
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use std::{
+        io::{BufReader, Cursor},
+        sync::{Arc, Mutex},
+    };
+
+    use ruinous_util::error::context::{ErrorContext, PackagedError};
+
+    use super::LexErrors;
+    use crate::{
+        reader::{error::FileError, CharReader},
+        source::SourceId,
+        span::Span,
+    };
+
+    #[test]
+    fn invalid_encoding_is_spanned() {
+        let input: &[u8] = b"\xEF\xBB\xBFlet a = 1;\nlet b = \"\xFF\";\n";
+
+        let error = CharReader::new(BufReader::new(input), None)
+            .collect()
+            .err()
+            .unwrap();
+
+        let context = Arc::new(Mutex::new(ErrorContext::new(Cursor::new(input))));
+
+        assert_eq!(
+            PackagedError::new(context, error).to_string(),
+            "error: 1:9: Invalid UTF-8 sequence [FF]\n\n\t> let b = \"\u{FFFD}\";\n\t>          ^"
+        );
+    }
+
+    #[test]
+    fn utf16_errors_are_rendered_decoded() {
+        let utf16 = |text: &str| {
+            text.encode_utf16()
+                .flat_map(u16::to_le_bytes)
+                .collect::<Vec<_>>()
+        };
+
+        let mut input = b"\xFF\xFE".to_vec();
+        input.extend(utf16("let \u{e9} = 1;\nlet b = \""));
+        input.extend(b"\x00\xD8");
+        input.extend(utf16("\";\n"));
+
+        let error = CharReader::new(BufReader::new(&input[..]), None)
+            .collect()
+            .err()
+            .unwrap();
+
+        let context = Arc::new(Mutex::new(ErrorContext::new(Cursor::new(&input[..]))));
+
+        assert_eq!(
+            PackagedError::new(context.clone(), error).to_string(),
+            "error: 1:9: Invalid UTF-16LE sequence [00, D8]\n\n\t> let b = \"\u{FFFD}\";\n\t>          ^"
+        );
+
+        assert_eq!(
+            context
+                .lock()
+                .unwrap()
+                .line(SourceId::default(), 0)
+                .unwrap(),
+            "let \u{e9} = 1;"
+        );
+    }
+
+    #[test]
+    fn lex_errors_are_rendered_in_order() {
+        let errors = LexErrors::from(vec![
+            FileError::invalid_edit(Span::new(Default::default(), Default::default(), ()), 1),
+            FileError::invalid_edit(Span::new(Default::default(), Default::default(), ()), 2),
+        ]);
+
+        let context = Arc::new(Mutex::new(ErrorContext::new(Cursor::new(""))));
+
+        assert_eq!(
+            PackagedError::new(context, errors).to_string(),
+            "error: Invalid edit of 0..0 in text of length 1\n\n\nerror: Invalid edit of 0..0 in text of length 2\n"
+        );
+    }
+}
@@ -0,0 +1,389 @@
+use std::ops::Range;
+
+use ruinous_util::span::{Location, Span};
+
+use crate::reader::{error::FileError, CharReader};
+
+use super::state::{Checkpoint, Continuation, Shift, Sink};
+
+/// The tokens replaced by an edit, as indices into the token list before (`removed`)
+/// and after (`inserted`) the edit.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Changed {
+    pub removed: Range<usize>,
+    pub inserted: Range<usize>,
+}
+
+/// Lexes a string in memory, and re-lexes only what changes when it's edited.
+pub struct IncrementalLexer<S: Checkpoint> {
+    text: String,
+    tokens: Vec<Span<S::Token>>,
+    errors: Vec<S::Error>,
+    checkpoints: Vec<Saved<S>>,
+}
+
+struct Saved<S> {
+    location: Location,
+    tokens: usize,
+    errors: usize,
+    state: S,
+}
+
+struct Previous<S: Checkpoint> {
+    removed_end: usize,
+    edit_end: usize,
+    tokens: Vec<Span<S::Token>>,
+    first_error: usize,
+    errors: Vec<S::Error>,
+    checkpoints: Vec<Saved<S>>,
+}
+
+struct Output<'a, Token, Error> {
+    tokens: &'a mut Vec<Span<Token>>,
+    errors: &'a mut Vec<Error>,
+}
+
+impl<S: Checkpoint> IncrementalLexer<S> {
+    pub fn new<T: Into<String>>(text: T, state: S) -> Result<Self, FileError> {
+        let mut lexer = Self {
+            text: text.into(),
+            tokens: Vec::new(),
+            errors: Vec::new(),
+            checkpoints: Vec::new(),
+        };
+
+        lexer.checkpoints.push(Saved {
+            location: Location::default(),
+            tokens: 0,
+            errors: 0,
+            state,
+        });
+
+        lexer.lex(None)?;
+        Ok(lexer)
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn tokens(&self) -> &[Span<S::Token>] {
+        &self.tokens
+    }
+
+    pub fn errors(&self) -> &[S::Error] {
+        &self.errors
+    }
+
+    /// Replace the text covered by `span` with `text`, returning the tokens that changed.
+    pub fn edit<Data>(&mut self, span: &Span<Data>, text: &str) -> Result<Changed, FileError> {
+        let range = span.range();
+
+        if range.start > range.end
+            || !self.text.is_char_boundary(range.start)
+            || !self.text.is_char_boundary(range.end)
+        {
+            return Err(FileError::invalid_edit(span.swap(()), self.text.len()));
+        }
+
+        self.text.replace_range(range.clone(), text);
+
+        let restart = self
+            .checkpoints
+            .partition_point(|saved| saved.location.offset <= range.start)
+            .saturating_sub(1);
+
+        let checkpoints = self.checkpoints.split_off(restart + 1);
+        let first = self.checkpoints[restart].tokens;
+        let first_error = self.checkpoints[restart].errors;
+
+        let previous = Previous {
+            removed_end: range.end,
+            edit_end: range.start + text.len(),
+            tokens: self.tokens.split_off(first),
+            first_error,
+            errors: self.errors.split_off(first_error),
+            checkpoints,
+        };
+
+        self.lex(Some(previous))
+    }
+
+    fn lex(&mut self, mut previous: Option<Previous<S>>) -> Result<Changed, FileError> {
+        let text = std::mem::take(&mut self.text);
+        let saved = self
+            .checkpoints
+            .last()
+            .expect("there is always a first checkpoint");
+
+        let start = saved.location;
+        let first = saved.tokens;
+        let mut state = saved.state.clone();
+
+        let origin = Shift::new(Location::default(), start);
+        let mut reader = CharReader::with_str(&text[start.offset..]);
+
+        let changed = loop {
+            let char = match reader.next_char() {
+                Ok(Some(char)) => char,
+                Ok(None) => {
+                    state.finish(&mut Output {
+                        tokens: &mut self.tokens,
+                        errors: &mut self.errors,
+                    });
+
+                    let removed = previous.map_or(0, |previous| previous.tokens.len());
+
+                    break Ok(Changed {
+                        removed: first..first + removed,
+                        inserted: first..self.tokens.len(),
+                    });
+                }
+                Err(error) => break Err(error),
+            };
+
+            let char = origin.span(char);
+
+            if char.start.col == 0 && char.start != start {
+                if let Some(changed) = previous
+                    .as_mut()
+                    .and_then(|previous| self.converge(previous, first, &state, char.start))
+                {
+                    break Ok(changed);
+                }
+
+                self.checkpoints.push(Saved {
+                    location: char.start,
+                    tokens: self.tokens.len(),
+                    errors: self.errors.len(),
+                    state: state.clone(),
+                });
+            }
+
+            let mut output = Output {
+                tokens: &mut self.tokens,
+                errors: &mut self.errors,
+            };
+
+            while let Continuation::Peek = state.process(char, &mut output) {}
+        };
+
+        self.text = text;
+        changed
+    }
+
+    fn converge(
+        &mut self,
+        previous: &mut Previous<S>,
+        first: usize,
+        state: &S,
+        location: Location,
+    ) -> Option<Changed> {
+        let anchor = state.pending().unwrap_or(location);
+
+        if anchor.offset < previous.edit_end {
+            return None;
+        }
+
+        let offset = location.offset - previous.edit_end + previous.removed_end;
+
+        let index = previous
+            .checkpoints
+            .binary_search_by_key(&offset, |saved| saved.location.offset)
+            .ok()?;
+
+        let saved = &previous.checkpoints[index];
+
+        if !state.converges(&saved.state) {
+            return None;
+        }
+
+        let shift = Shift::new(saved.state.pending().unwrap_or(saved.location), anchor);
+
+        let inserted = first..self.tokens.len();
+        let removed = first..saved.tokens;
+
+        let reused = saved.tokens - first;
+        let errors = (saved.errors, self.errors.len());
+
+        self.tokens.extend(
+            previous
+                .tokens
+                .drain(reused..)
+                .map(|token| shift.span(token)),
+        );
+
+        self.errors.extend(
+            previous
+                .errors
+                .drain(saved.errors - previous.first_error..)
+                .map(|mut error| {
+                    S::shift_error(&mut error, shift);
+                    error
+                }),
+        );
+
+        self.checkpoints
+            .extend(previous.checkpoints.drain(index..).map(|mut saved| {
+                saved.location = shift.apply(saved.location);
+                saved.tokens = saved.tokens + inserted.end - removed.end;
+                saved.errors = saved.errors + errors.1 - errors.0;
+                saved.state.shift(shift);
+                saved
+            }));
+
+        Some(Changed { removed, inserted })
+    }
+}
+
+impl<'a, Token, Error> Sink<Token, Error> for Output<'a, Token, Error> {
+    fn token(&mut self, token: Span<Token>) {
+        self.tokens.push(token);
+    }
+
+    fn error(&mut self, error: Error) {
+        self.errors.push(error);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::lexer::{
+        indent::Indented,
+        rules::{RuleLexer, Rules},
+    };
+
+    fn rules() -> RuleLexer<String> {
+        Rules::new()
+            .regex(r"[a-z]+|\d+|[{}]", str::to_owned)
+            .and_then(|rules| rules.regex(r#""[^"]*""#, str::to_owned))
+            .and_then(|rules| rules.skip(r"\s+"))
+            .unwrap()
+            .build()
+    }
+
+    fn edit<S: Checkpoint>(
+        lexer: &mut IncrementalLexer<S>,
+        start: usize,
+        end: usize,
+        text: &str,
+    ) -> Changed {
+        let location = |offset| Location {
+            offset,
+            ..Location::default()
+        };
+
+        lexer
+            .edit(&Span::new(location(start), location(end), ()), text)
+            .unwrap()
+    }
+
+    fn assert_relexed<S: Checkpoint>(lexer: &IncrementalLexer<S>, state: S)
+    where
+        S::Token: PartialEq + std::fmt::Debug,
+    {
+        let fresh = IncrementalLexer::new(lexer.text(), state).unwrap();
+        assert_eq!(lexer.tokens(), fresh.tokens());
+        assert_eq!(
+            format!("{:?}", lexer.errors()),
+            format!("{:?}", fresh.errors())
+        );
+    }
+
+    #[test]
+    fn incremental_edits_relex_locally() {
+        let mut lexer = IncrementalLexer::new("a { b }\nc 12\nd \"e\"\nf\n", rules()).unwrap();
+        assert_eq!(lexer.tokens().len(), 9);
+
+        let changed = edit(&mut lexer, 10, 12, "345 g\nh");
+        assert_eq!(lexer.text(), "a { b }\nc 345 g\nh\nd \"e\"\nf\n");
+        assert_eq!(
+            changed,
+            Changed {
+                removed: 4..6,
+                inserted: 4..8
+            }
+        );
+        assert_relexed(&lexer, rules());
+
+        let changed = edit(&mut lexer, 2, 3, "");
+        assert_eq!(
+            changed,
+            Changed {
+                removed: 0..4,
+                inserted: 0..3
+            }
+        );
+        assert_relexed(&lexer, rules());
+
+        let changed = edit(&mut lexer, 19, 20, "\"\n");
+        assert_eq!(lexer.text(), "a  b }\nc 345 g\nh\nd \"\ne\"\nf\n");
+        assert_eq!(
+            changed,
+            Changed {
+                removed: 7..9,
+                inserted: 7..9
+            }
+        );
+        assert_relexed(&lexer, rules());
+
+        let end = lexer.text().len();
+        edit(&mut lexer, end, end, "i");
+        assert_relexed(&lexer, rules());
+    }
+
+    #[test]
+    fn incremental_edits_indented_blocks() {
+        let state = || Indented::new(rules()).tab_width(4);
+        let mut lexer = IncrementalLexer::new("a\n  b\n  c\nd\n", state()).unwrap();
+
+        let changed = edit(&mut lexer, 6, 6, "  ");
+        assert_eq!(lexer.text(), "a\n  b\n    c\nd\n");
+        assert!(changed.inserted.len() < lexer.tokens().len());
+        assert_relexed(&lexer, state());
+
+        edit(&mut lexer, 0, 2, "");
+        assert_relexed(&lexer, state());
+    }
+
+    #[test]
+    fn incremental_edits_reuse_errors_after_the_edit() {
+        let mut lexer = IncrementalLexer::new("a ?\nb\nc\nd ?\ne\n", rules()).unwrap();
+        assert_eq!(lexer.errors().len(), 2);
+
+        let changed = edit(&mut lexer, 4, 5, "bb b");
+        assert_eq!(
+            changed,
+            Changed {
+                removed: 1..2,
+                inserted: 1..3
+            }
+        );
+        assert_relexed(&lexer, rules());
+
+        edit(&mut lexer, 2, 3, "");
+        assert_eq!(lexer.errors().len(), 1);
+        assert_relexed(&lexer, rules());
+    }
+
+    #[test]
+    fn incremental_edits_reject_invalid_spans() {
+        let mut lexer = IncrementalLexer::new("é\n", rules()).unwrap();
+
+        let location = |offset| Location {
+            offset,
+            ..Location::default()
+        };
+
+        for (start, end) in [(1, 1), (0, 4), (2, 1)] {
+            let span = Span::new(location(start), location(end), ());
+            assert!(matches!(
+                lexer.edit(&span, "a"),
+                Err(FileError::InvalidEdit { length: 3, .. })
+            ));
+        }
+
+        assert_eq!(lexer.text(), "é\n");
+    }
+}
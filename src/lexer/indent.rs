@@ -0,0 +1,477 @@
+use ruinous_util::{
+    error::{context::ErrorProvider, writer::ErrorWriter},
+    span::{Location, Span},
+};
+
+use super::state::{Checkpoint, Continuation, Shift, Sink, State};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Indentation<Token> {
+    Indent,
+    Dedent,
+    Newline,
+    Token(Token),
+}
+
+#[derive(Debug)]
+pub enum IndentError<E> {
+    Inner(E),
+    InconsistentDedent(Span<()>),
+    MixedIndentation(Span<()>),
+}
+
+/// Wraps a state to produce indentation tokens. The inner state still sees every char,
+/// so it should skip whitespace, and lines where it produces no tokens count as blank.
+#[derive(Clone)]
+pub struct Indented<S> {
+    inner: S,
+    layout: Layout,
+    end: Location,
+}
+
+#[derive(Clone)]
+struct Layout {
+    tab_width: usize,
+    levels: Vec<usize>,
+    style: Option<char>,
+    content: bool,
+    leading: bool,
+    current: Measure,
+    newlines: Vec<(Span<()>, Measure)>,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct Measure {
+    start: Option<Location>,
+    width: usize,
+    tabs: bool,
+    spaces: bool,
+}
+
+struct InnerSink<'a, S: ?Sized> {
+    sink: &'a mut S,
+    layout: &'a mut Layout,
+}
+
+impl<S: State> Indented<S> {
+    pub fn new(inner: S) -> Self {
+        Self {
+            inner,
+            layout: Layout {
+                tab_width: 8,
+                levels: vec![0],
+                style: None,
+                content: false,
+                leading: true,
+                current: Measure::default(),
+                newlines: Vec::new(),
+            },
+            end: Location::default(),
+        }
+    }
+
+    #[must_use]
+    pub fn tab_width(mut self, tab_width: usize) -> Self {
+        self.layout.tab_width = tab_width;
+        self
+    }
+}
+
+impl Layout {
+    fn consume(&mut self, input: Span<char>) {
+        match input.data {
+            '\n' => {
+                self.newlines.push((input.swap(()), self.current));
+                self.current = Measure::default();
+                self.leading = true;
+            }
+            ' ' | '\t' if self.leading => {
+                let current = &mut self.current;
+                current.start.get_or_insert(input.start);
+
+                if input.data == '\t' {
+                    current.width += self.tab_width - current.width % self.tab_width.max(1);
+                    current.tabs = true;
+                } else {
+                    current.width += 1;
+                    current.spaces = true;
+                }
+            }
+            _ => self.leading = false,
+        }
+    }
+
+    fn token<Token, Error, K>(&mut self, token: Span<()>, sink: &mut K)
+    where
+        K: Sink<Indentation<Token>, IndentError<Error>> + ?Sized,
+    {
+        let line = self
+            .newlines
+            .iter()
+            .position(|(newline, _)| newline.start >= token.start);
+
+        if line != Some(0) && !self.newlines.is_empty() {
+            if self.content {
+                sink.token(self.newlines[0].0.swap(Indentation::Newline));
+            }
+
+            self.content = false;
+        }
+
+        if !self.content {
+            let measure = line.map_or(self.current, |line| self.newlines[line].1);
+            self.indent(measure, token.start, sink);
+            self.content = true;
+        }
+
+        self.newlines
+            .retain(|(newline, _)| newline.start >= token.end);
+    }
+
+    fn indent<Token, Error, K>(&mut self, measure: Measure, start: Location, sink: &mut K)
+    where
+        K: Sink<Indentation<Token>, IndentError<Error>> + ?Sized,
+    {
+        let span = Span::new(measure.start.unwrap_or(start), start, ());
+
+        if measure.tabs && measure.spaces {
+            sink.error(IndentError::MixedIndentation(span));
+        } else if measure.tabs || measure.spaces {
+            let style = if measure.tabs { '\t' } else { ' ' };
+
+            if *self.style.get_or_insert(style) != style {
+                sink.error(IndentError::MixedIndentation(span));
+            }
+        }
+
+        let current = self.levels.last().copied().unwrap_or(0);
+
+        if measure.width > current {
+            self.levels.push(measure.width);
+            sink.token(span.swap(Indentation::Indent));
+        } else {
+            while self
+                .levels
+                .last()
+                .is_some_and(|&level| level > measure.width)
+            {
+                self.levels.pop();
+                sink.token(Span::new(start, start, Indentation::Dedent));
+            }
+
+            if self.levels.last() != Some(&measure.width) {
+                sink.error(IndentError::InconsistentDedent(span));
+            }
+        }
+    }
+}
+
+impl Measure {
+    fn converges(&self, other: &Self) -> bool {
+        self.start.is_some() == other.start.is_some()
+            && self.width == other.width
+            && self.tabs == other.tabs
+            && self.spaces == other.spaces
+    }
+
+    fn shift(&mut self, shift: Shift) {
+        self.start = self.start.map(|start| shift.apply(start));
+    }
+}
+
+impl<S: State> State for Indented<S> {
+    type Token = Indentation<S::Token>;
+    type Error = IndentError<S::Error>;
+
+    fn process<K: Sink<Self::Token, Self::Error> + ?Sized>(
+        &mut self,
+        input: Span<char>,
+        sink: &mut K,
+    ) -> Continuation {
+        self.end = input.end;
+
+        let continuation = self.inner.process(
+            input,
+            &mut InnerSink {
+                sink,
+                layout: &mut self.layout,
+            },
+        );
+
+        if let Continuation::Consume = continuation {
+            self.layout.consume(input);
+        }
+
+        continuation
+    }
+
+    fn finish<K: Sink<Self::Token, Self::Error> + ?Sized>(mut self, sink: &mut K) {
+        self.inner.finish(&mut InnerSink {
+            sink,
+            layout: &mut self.layout,
+        });
+
+        if self.layout.content {
+            let newline = self
+                .layout
+                .newlines
+                .first()
+                .map_or(Span::new(self.end, self.end, ()), |(newline, _)| *newline);
+
+            sink.token(newline.swap(Indentation::Newline));
+        }
+
+        for _ in 1..self.layout.levels.len() {
+            sink.token(Span::new(self.end, self.end, Indentation::Dedent));
+        }
+    }
+
+    fn take_pending(&mut self) -> Vec<Span<char>> {
+        self.inner.take_pending()
+    }
+}
+
+impl<S: Checkpoint> Checkpoint for Indented<S> {
+    fn pending(&self) -> Option<Location> {
+        let layout = &self.layout;
+
+        [
+            layout.current.start,
+            layout.newlines.first().map(|(newline, _)| newline.start),
+            self.inner.pending(),
+        ]
+        .into_iter()
+        .flatten()
+        .min()
+    }
+
+    fn converges(&self, other: &Self) -> bool {
+        let (layout, other_layout) = (&self.layout, &other.layout);
+
+        self.inner.converges(&other.inner)
+            && layout.tab_width == other_layout.tab_width
+            && layout.levels == other_layout.levels
+            && layout.style == other_layout.style
+            && layout.content == other_layout.content
+            && layout.leading == other_layout.leading
+            && layout.current.converges(&other_layout.current)
+            && layout.newlines.len() == other_layout.newlines.len()
+            && layout
+                .newlines
+                .iter()
+                .zip(&other_layout.newlines)
+                .all(|((_, measure), (_, other))| measure.converges(other))
+    }
+
+    fn shift(&mut self, shift: Shift) {
+        self.inner.shift(shift);
+        self.layout.current.shift(shift);
+
+        for (newline, measure) in &mut self.layout.newlines {
+            *newline = shift.span(*newline);
+            measure.shift(shift);
+        }
+
+        self.end = shift.apply(self.end);
+    }
+
+    fn shift_error(error: &mut Self::Error, shift: Shift) {
+        match error {
+            IndentError::Inner(error) => S::shift_error(error, shift),
+            IndentError::InconsistentDedent(span) | IndentError::MixedIndentation(span) => {
+                *span = shift.span(*span);
+            }
+        }
+    }
+}
+
+impl<'a, Token, Error, S> Sink<Token, Error> for InnerSink<'a, S>
+where
+    S: Sink<Indentation<Token>, IndentError<Error>> + ?Sized,
+{
+    fn token(&mut self, token: Span<Token>) {
+        self.layout.token(token.swap(()), self.sink);
+        self.sink.token(token.map(Indentation::Token));
+    }
+
+    fn error(&mut self, error: Error) {
+        self.sink.error(IndentError::Inner(error));
+    }
+
+    fn interrupted(&self) -> bool {
+        self.sink.interrupted()
+    }
+}
+
+impl<E: ErrorProvider> ErrorProvider for IndentError<E> {
+    fn write_errors(&self, writer: &mut dyn ErrorWriter) -> std::fmt::Result {
+        match self {
+            IndentError::Inner(error) => error.write_errors(writer),
+            IndentError::InconsistentDedent(span) => writer.error(
+                Some(*span),
+                "Unindent does not match any outer indentation level",
+            ),
+            IndentError::MixedIndentation(span) => writer.error(
+                Some(*span),
+                "Inconsistent use of tabs and spaces in indentation",
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::lexer::{
+        rules::{RuleError, Rules},
+        Error, Lexer,
+    };
+
+    fn lex(input: &str) -> (Vec<Span<Indentation<String>>>, Vec<IndentError<RuleError>>) {
+        let rules = Rules::new()
+            .regex("[a-z:]+", str::to_owned)
+            .and_then(|rules| rules.regex(r#""[^"]*""#, str::to_owned))
+            .and_then(|rules| rules.skip(r"\s+"))
+            .and_then(|rules| rules.skip("#[^\n]*"))
+            .unwrap()
+            .build();
+
+        let mut tokens = Vec::new();
+        let result = Lexer::with_str(input).lex(Indented::new(rules).tab_width(4), |token| {
+            tokens.push(token);
+        });
+
+        let errors = match result {
+            Ok(()) => Vec::new(),
+            Err(Error::LexErrors(errors)) => errors.errors,
+            Err(error) => panic!("{error:?}"),
+        };
+
+        (tokens, errors)
+    }
+
+    fn token(text: &str) -> Indentation<String> {
+        Indentation::Token(text.to_owned())
+    }
+
+    #[test]
+    fn indent_blocks() {
+        let (tokens, errors) = lex("a:\n  b\n\n  c:\n      d\ne");
+
+        assert!(errors.is_empty(), "{errors:?}");
+        assert_eq!(
+            tokens
+                .iter()
+                .map(|token| token.data.clone())
+                .collect::<Vec<_>>(),
+            [
+                token("a:"),
+                Indentation::Newline,
+                Indentation::Indent,
+                token("b"),
+                Indentation::Newline,
+                token("c:"),
+                Indentation::Newline,
+                Indentation::Indent,
+                token("d"),
+                Indentation::Newline,
+                Indentation::Dedent,
+                Indentation::Dedent,
+                token("e"),
+                Indentation::Newline,
+            ]
+        );
+
+        let indent = &tokens[2];
+        assert_eq!(
+            (indent.start.col, indent.end.col, indent.start.line),
+            (0, 2, 1)
+        );
+    }
+
+    #[test]
+    fn indent_closes_blocks_at_end() {
+        let (tokens, _) = lex("a\n\tb");
+
+        assert_eq!(
+            tokens
+                .iter()
+                .map(|token| token.data.clone())
+                .collect::<Vec<_>>(),
+            [
+                token("a"),
+                Indentation::Newline,
+                Indentation::Indent,
+                token("b"),
+                Indentation::Newline,
+                Indentation::Dedent,
+            ]
+        );
+    }
+
+    #[test]
+    fn indent_errors() {
+        let (_, errors) = lex("a\n    b\n  c\n\t d\n");
+
+        assert!(matches!(
+            errors[..],
+            [
+                IndentError::InconsistentDedent(_),
+                IndentError::MixedIndentation(_),
+            ]
+        ));
+    }
+
+    #[test]
+    fn indent_ignores_newlines_inside_tokens() {
+        let (tokens, errors) = lex("a:\n  s \"x\n    y\"\n  b\nc");
+
+        assert!(errors.is_empty(), "{errors:?}");
+        assert_eq!(
+            tokens
+                .iter()
+                .map(|token| token.data.clone())
+                .collect::<Vec<_>>(),
+            [
+                token("a:"),
+                Indentation::Newline,
+                Indentation::Indent,
+                token("s"),
+                token("\"x\n    y\""),
+                Indentation::Newline,
+                token("b"),
+                Indentation::Newline,
+                Indentation::Dedent,
+                token("c"),
+                Indentation::Newline,
+            ]
+        );
+
+        assert_eq!(tokens[5].range(), 16..17);
+    }
+
+    #[test]
+    fn indent_treats_comment_lines_as_blank() {
+        let (tokens, errors) = lex("a:\n  b\n# note\n      # more\n  c\nd");
+
+        assert!(errors.is_empty(), "{errors:?}");
+        assert_eq!(
+            tokens
+                .iter()
+                .map(|token| token.data.clone())
+                .collect::<Vec<_>>(),
+            [
+                token("a:"),
+                Indentation::Newline,
+                Indentation::Indent,
+                token("b"),
+                Indentation::Newline,
+                token("c"),
+                Indentation::Newline,
+                Indentation::Dedent,
+                token("d"),
+                Indentation::Newline,
+            ]
+        );
+    }
+}
@@ -1,30 +1,90 @@
 use std::{
     fs::File,
-    io::{BufRead, BufReader, Cursor},
-    path::Path,
+    io::{BufRead, BufReader, Cursor, Read, StdinLock},
+    path::{Path, PathBuf},
 };
 
-use ruinous_util::span::Span;
+use ruinous_util::{
+    source::{SharedBuffer, SourceMap},
+    span::{Columns, Span},
+};
+
+use crate::reader::{error::FileError, CharReader, Encoding};
 
-use crate::reader::{error::FileError, CharReader};
+use self::state::{LineState, Sink, State};
 
-use self::state::State;
+pub use ruinous_macros::Lexer;
+
+pub use self::{
+    error::{Error, LexErrors},
+    tokens::Tokens,
+};
 
-pub use self::error::Error;
+#[cfg(feature = "async")]
+pub use self::asynchronous::AsyncLexer;
 
+pub mod incremental;
+pub mod indent;
+pub mod modal;
+pub mod rules;
 pub mod state;
+pub mod substates;
+pub mod trivia;
 
+#[cfg(feature = "async")]
+mod asynchronous;
 mod error;
+mod tokens;
 
 pub struct Lexer<R> {
     reader: CharReader<R>,
 }
 
+struct CallbackSink<'a, Callback, Error> {
+    callback: &'a mut Callback,
+    errors: Vec<Error>,
+}
+
 impl Lexer<BufReader<File>> {
     pub fn with_file<P: AsRef<Path>>(path: P) -> Result<Self, FileError> {
         let reader = CharReader::with_file(path)?;
         Ok(Self { reader })
     }
+
+    pub fn with_file_in<P: AsRef<Path>>(
+        sources: &mut SourceMap,
+        path: P,
+    ) -> Result<Self, FileError> {
+        let reader = CharReader::with_file_in(sources, path)?;
+        Ok(Self { reader })
+    }
+}
+
+impl Lexer<StdinLock<'static>> {
+    pub fn with_stdin() -> Self {
+        let reader = CharReader::with_stdin();
+        Self { reader }
+    }
+}
+
+impl<R: Read> Lexer<BufReader<R>> {
+    pub fn with_reader<N: Into<PathBuf>>(name: N, reader: R) -> Self {
+        let reader = CharReader::with_reader(name, reader);
+        Self { reader }
+    }
+}
+
+impl Lexer<Cursor<SharedBuffer>> {
+    pub fn with_buffer<B: Into<SharedBuffer>>(buffer: B) -> Self {
+        let reader = CharReader::with_buffer(buffer);
+        Self { reader }
+    }
+
+    #[cfg(feature = "mmap")]
+    pub fn with_mmap<P: AsRef<Path>>(path: P) -> Result<Self, FileError> {
+        let reader = CharReader::with_mmap(path)?;
+        Ok(Self { reader })
+    }
 }
 
 impl<'a> Lexer<Cursor<&'a str>> {
@@ -35,13 +95,90 @@ impl<'a> Lexer<Cursor<&'a str>> {
 }
 
 impl<R: BufRead> Lexer<R> {
+    #[must_use]
+    pub fn lossless(self) -> Self {
+        Self {
+            reader: self.reader.lossless(),
+        }
+    }
+
+    #[must_use]
+    pub fn encoding(self, encoding: Encoding) -> Self {
+        Self {
+            reader: self.reader.encoding(encoding),
+        }
+    }
+
+    #[must_use]
+    pub fn lossy(self) -> Self {
+        Self {
+            reader: self.reader.lossy(),
+        }
+    }
+
+    #[must_use]
+    pub fn columns(self, columns: Columns) -> Self {
+        Self {
+            reader: self.reader.columns(columns),
+        }
+    }
+
     pub fn lex<S: State, Callback: FnMut(Span<S::Token>)>(
         self,
         mut state: S,
         mut callback: Callback,
     ) -> Result<(), Error<S>> {
-        self.reader
-            .read(|span| state.process(span, &mut callback))?;
-        state.finish().map_err(Error::LexError)
+        let mut sink = CallbackSink {
+            callback: &mut callback,
+            errors: Vec::new(),
+        };
+
+        self.reader.read(|span| state.process(span, &mut sink))?;
+        state.finish(&mut sink);
+
+        if sink.errors.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::LexErrors(sink.errors.into()))
+        }
+    }
+
+    pub fn lex_lines<S: LineState, Callback: FnMut(Span<S::Token>)>(
+        mut self,
+        mut state: S,
+        mut callback: Callback,
+    ) -> Result<(), Error<S>> {
+        let mut sink = CallbackSink {
+            callback: &mut callback,
+            errors: Vec::new(),
+        };
+
+        while let Some(line) = self.reader.next_line()? {
+            state.process_line(line, &mut sink);
+        }
+
+        state.finish(&mut sink);
+
+        if sink.errors.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::LexErrors(sink.errors.into()))
+        }
+    }
+
+    pub fn tokens<S: State>(self, state: S) -> Tokens<R, S> {
+        Tokens::new(self.reader, state)
+    }
+}
+
+impl<'a, Token, Error, Callback: FnMut(Span<Token>)> Sink<Token, Error>
+    for CallbackSink<'a, Callback, Error>
+{
+    fn token(&mut self, token: Span<Token>) {
+        (self.callback)(token);
+    }
+
+    fn error(&mut self, error: Error) {
+        self.errors.push(error);
     }
 }
@@ -0,0 +1,454 @@
+use std::fmt::Debug;
+
+use ruinous_util::{
+    error::{context::ErrorProvider, writer::ErrorWriter},
+    span::{Location, Span},
+};
+
+use super::state::{Continuation, Sink, State};
+
+pub trait Mode<Token, Error> {
+    fn process(&mut self, input: Span<char>, sink: &mut dyn Sink<Token, Error>) -> Continuation;
+    fn finish(self: Box<Self>, sink: &mut dyn Sink<Token, Error>);
+    fn take_pending(&mut self) -> Vec<Span<char>>;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Transition<M> {
+    Push(M),
+    Pop,
+    Replace(M),
+}
+
+#[derive(Debug)]
+pub enum ModalError<M, E> {
+    Mode(E),
+    Unclosed(Span<M>),
+    UnknownMode(Span<M>),
+}
+
+pub type Transitions<M, Token> = dyn Fn(&M, &Token) -> Option<Transition<M>>;
+
+pub struct Modal<M, Token, Error> {
+    modes: Vec<(M, Box<dyn Mode<Token, Error>>)>,
+    stack: Vec<(usize, Location)>,
+    transitions: Box<Transitions<M, Token>>,
+    unfed: Vec<Span<char>>,
+}
+
+struct ModeSink<'a, M, Token, S: ?Sized> {
+    mode: &'a M,
+    transitions: &'a Transitions<M, Token>,
+    pending: Vec<(Transition<M>, Location)>,
+    sink: &'a mut S,
+}
+
+impl<S: State> Mode<S::Token, S::Error> for S {
+    fn process(
+        &mut self,
+        input: Span<char>,
+        sink: &mut dyn Sink<S::Token, S::Error>,
+    ) -> Continuation {
+        State::process(self, input, sink)
+    }
+
+    fn finish(self: Box<Self>, sink: &mut dyn Sink<S::Token, S::Error>) {
+        State::finish(*self, sink);
+    }
+
+    fn take_pending(&mut self) -> Vec<Span<char>> {
+        State::take_pending(self)
+    }
+}
+
+impl<M: PartialEq, Token, Error> Modal<M, Token, Error> {
+    pub fn new<S>(mode: M, state: S) -> Self
+    where
+        S: State<Token = Token, Error = Error> + 'static,
+    {
+        Self {
+            modes: vec![(mode, Box::new(state))],
+            stack: vec![(0, Location::default())],
+            transitions: Box::new(|_, _| None),
+            unfed: Vec::new(),
+        }
+    }
+
+    #[must_use]
+    pub fn mode<S>(mut self, mode: M, state: S) -> Self
+    where
+        S: State<Token = Token, Error = Error> + 'static,
+    {
+        self.modes.push((mode, Box::new(state)));
+        self
+    }
+
+    #[must_use]
+    pub fn transitions<F>(self, transitions: F) -> Self
+    where
+        F: Fn(&M, &Token) -> Option<Transition<M>> + 'static,
+    {
+        Self {
+            transitions: Box::new(transitions),
+            ..self
+        }
+    }
+
+    fn find(&self, mode: M, location: Location) -> Result<usize, ModalError<M, Error>> {
+        self.modes
+            .iter()
+            .position(|(found, _)| *found == mode)
+            .ok_or_else(|| ModalError::UnknownMode(Span::new(location, location, mode)))
+    }
+
+    fn apply(
+        &mut self,
+        transition: Transition<M>,
+        location: Location,
+    ) -> Result<(), ModalError<M, Error>> {
+        match transition {
+            Transition::Push(mode) => {
+                let index = self.find(mode, location)?;
+                self.stack.push((index, location));
+            }
+            Transition::Pop => {
+                if self.stack.len() > 1 {
+                    self.stack.pop();
+                }
+            }
+            Transition::Replace(mode) => {
+                let index = self.find(mode, location)?;
+
+                if let Some(top) = self.stack.last_mut() {
+                    top.0 = index;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl<M, Token, Error> State for Modal<M, Token, Error>
+where
+    M: PartialEq + Clone + Debug,
+    Error: ErrorProvider,
+{
+    type Token = Token;
+    type Error = ModalError<M, Error>;
+
+    fn process<S: Sink<Self::Token, Self::Error> + ?Sized>(
+        &mut self,
+        input: Span<char>,
+        sink: &mut S,
+    ) -> Continuation {
+        let current = self.stack.last().map_or(0, |&(index, _)| index);
+        let (mode, state) = &mut self.modes[current];
+
+        let mut mode_sink = ModeSink {
+            mode,
+            transitions: &*self.transitions,
+            pending: Vec::new(),
+            sink,
+        };
+
+        let continuation = state.process(input, &mut mode_sink);
+        let transitions = mode_sink.pending;
+
+        let pending = if transitions.is_empty() {
+            Vec::new()
+        } else {
+            state.take_pending()
+        };
+
+        for (transition, location) in transitions {
+            if let Err(error) = self.apply(transition, location) {
+                sink.error(error);
+            }
+        }
+
+        let mut pending = pending.into_iter();
+
+        while !sink.interrupted() {
+            let Some(char) = pending.next() else {
+                break;
+            };
+
+            while let Continuation::Peek = State::process(self, char, sink) {}
+        }
+
+        self.unfed.extend(pending);
+        continuation
+    }
+
+    fn finish<S: Sink<Self::Token, Self::Error> + ?Sized>(self, sink: &mut S) {
+        for &(index, location) in &self.stack[1..] {
+            let mode = self.modes[index].0.clone();
+            sink.error(ModalError::Unclosed(Span::new(location, location, mode)));
+        }
+
+        for (mode, state) in self.modes {
+            let mut mode_sink = ModeSink {
+                mode: &mode,
+                transitions: &|_: &M, _: &Token| None,
+                pending: Vec::new(),
+                sink,
+            };
+
+            state.finish(&mut mode_sink);
+        }
+    }
+
+    fn take_pending(&mut self) -> Vec<Span<char>> {
+        let current = self.stack.last().map_or(0, |&(index, _)| index);
+        let mut pending = self.modes[current].1.take_pending();
+        pending.append(&mut self.unfed);
+        pending
+    }
+}
+
+impl<'a, M, Token, Error, S> Sink<Token, Error> for ModeSink<'a, M, Token, S>
+where
+    S: Sink<Token, ModalError<M, Error>> + ?Sized,
+{
+    fn token(&mut self, token: Span<Token>) {
+        if let Some(transition) = (self.transitions)(self.mode, &token.data) {
+            self.pending.push((transition, token.start));
+        }
+
+        self.sink.token(token);
+    }
+
+    fn error(&mut self, error: Error) {
+        self.sink.error(ModalError::Mode(error));
+    }
+
+    fn interrupted(&self) -> bool {
+        !self.pending.is_empty() || self.sink.interrupted()
+    }
+}
+
+impl<M: Debug, E: ErrorProvider> ErrorProvider for ModalError<M, E> {
+    fn write_errors(&self, writer: &mut dyn ErrorWriter) -> std::fmt::Result {
+        match self {
+            ModalError::Mode(error) => error.write_errors(writer),
+            ModalError::Unclosed(span) => writer.error(
+                Some(span.swap(())),
+                &format!("Unclosed {:?} at end of input", span.data),
+            ),
+            ModalError::UnknownMode(span) => writer.error(
+                Some(span.swap(())),
+                &format!("Transition to unregistered mode {:?}", span.data),
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::lexer::{
+        rules::{RuleError, Rules},
+        substates::{Identifier, SubStates, Symbols},
+        Error, Lexer,
+    };
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    enum Context {
+        Code,
+        Str,
+    }
+
+    #[derive(Debug, Clone, PartialEq)]
+    enum Token {
+        Ident(String),
+        Text(String),
+        Quote,
+        Block,
+        Open,
+        Close,
+    }
+
+    fn modal() -> Modal<Context, Token, RuleError> {
+        let code = Rules::new()
+            .literal("\"", |_| Token::Quote)
+            .literal("}", |_| Token::Close)
+            .regex("[a-z]+", |text| Token::Ident(text.to_owned()))
+            .and_then(|rules| rules.skip(r"\s+"))
+            .unwrap()
+            .build();
+
+        let string = Rules::new()
+            .literal("\"", |_| Token::Quote)
+            .literal("${", |_| Token::Open)
+            .regex("[^\"$]+", |text| Token::Text(text.to_owned()))
+            .unwrap()
+            .build();
+
+        Modal::new(Context::Code, code)
+            .mode(Context::Str, string)
+            .transitions(|mode, token| match (mode, token) {
+                (Context::Code, Token::Quote) => Some(Transition::Push(Context::Str)),
+                (Context::Str, Token::Open) => Some(Transition::Push(Context::Code)),
+                (_, Token::Quote | Token::Close) => Some(Transition::Pop),
+                _ => None,
+            })
+    }
+
+    #[test]
+    fn modal_string_interpolation() {
+        let mut tokens = Vec::new();
+
+        Lexer::with_str(r#"say "a ${ x } b""#)
+            .lex(modal(), |token| tokens.push(token.data))
+            .unwrap();
+
+        assert_eq!(
+            tokens,
+            [
+                Token::Ident("say".to_owned()),
+                Token::Quote,
+                Token::Text("a ".to_owned()),
+                Token::Open,
+                Token::Ident("x".to_owned()),
+                Token::Close,
+                Token::Text(" b".to_owned()),
+                Token::Quote,
+            ]
+        );
+    }
+
+    #[test]
+    fn modal_report_unclosed_modes() {
+        let mut tokens = Vec::new();
+        let result = Lexer::with_str("\"a ${ b").lex(modal(), |token| tokens.push(token.data));
+
+        let Err(Error::LexErrors(errors)) = result else {
+            panic!("expected unclosed modes to be reported");
+        };
+
+        let unclosed = errors
+            .errors
+            .iter()
+            .filter_map(|error| match error {
+                ModalError::Unclosed(span) => Some((span.data, span.start.col)),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(unclosed, [(Context::Str, 0), (Context::Code, 3)]);
+    }
+
+    #[test]
+    fn modal_transition_replays_lookahead() {
+        let code = Rules::new()
+            .literal("\"", |_| Token::Quote)
+            .literal("\"\"\"", |_| Token::Block)
+            .regex("[a-z]+", |text| Token::Ident(text.to_owned()))
+            .and_then(|rules| rules.skip(r"\s+"))
+            .unwrap()
+            .build();
+
+        let string = Rules::new()
+            .literal("\"", |_| Token::Quote)
+            .regex("[^\"]+", |text| Token::Text(text.to_owned()))
+            .unwrap()
+            .build();
+
+        let modal = Modal::new(Context::Code, code)
+            .mode(Context::Str, string)
+            .transitions(|mode, token| match (mode, token) {
+                (Context::Code, Token::Quote) => Some(Transition::Push(Context::Str)),
+                (Context::Str, Token::Quote) => Some(Transition::Pop),
+                _ => None,
+            });
+
+        let mut tokens = Vec::new();
+
+        Lexer::with_str(r#"x "a b""c" "" """ y"#)
+            .lex(modal, |token| tokens.push(token.data))
+            .unwrap();
+
+        assert_eq!(
+            tokens,
+            [
+                Token::Ident("x".to_owned()),
+                Token::Quote,
+                Token::Text("a b".to_owned()),
+                Token::Quote,
+                Token::Quote,
+                Token::Text("c".to_owned()),
+                Token::Quote,
+                Token::Quote,
+                Token::Quote,
+                Token::Block,
+                Token::Ident("y".to_owned()),
+            ]
+        );
+    }
+
+    #[test]
+    fn modal_substates_hand_back_lookahead() {
+        let code = SubStates::new()
+            .with(Identifier::new(), Token::Ident)
+            .with(Symbols::new([("\"", Token::Quote)]), |quote| quote);
+
+        let text = Identifier::new()
+            .start(|char| char != '"')
+            .continues(|char| char != '"');
+
+        let string = SubStates::new()
+            .with(text, Token::Text)
+            .with(Symbols::new([("\"", Token::Quote)]), |quote| quote)
+            .skip_chars(|_| false);
+
+        let modal = Modal::new(Context::Code, code)
+            .mode(Context::Str, string)
+            .transitions(|mode, token| match (mode, token) {
+                (Context::Code, Token::Quote) => Some(Transition::Push(Context::Str)),
+                (Context::Str, Token::Quote) => Some(Transition::Pop),
+                _ => None,
+            });
+
+        let mut tokens = Vec::new();
+
+        Lexer::with_str(r#"x "a b" y"#)
+            .lex(modal, |token| tokens.push(token.data))
+            .unwrap();
+
+        assert_eq!(
+            tokens,
+            [
+                Token::Ident("x".to_owned()),
+                Token::Quote,
+                Token::Text("a b".to_owned()),
+                Token::Quote,
+                Token::Ident("y".to_owned()),
+            ]
+        );
+    }
+
+    #[test]
+    fn modal_reports_unknown_modes() {
+        let modal = modal().transitions(|_, token| match token {
+            Token::Quote => Some(Transition::Push(Context::Str)),
+            _ => None,
+        });
+
+        let modal = Modal {
+            modes: modal.modes.into_iter().take(1).collect(),
+            ..modal
+        };
+
+        let result = Lexer::with_str("a \"b").lex(modal, |_| ());
+
+        let Err(Error::LexErrors(errors)) = result else {
+            panic!("expected the unknown mode to be reported");
+        };
+
+        assert!(matches!(
+            &errors.errors[..],
+            [ModalError::UnknownMode(span), ..] if span.data == Context::Str && span.start.col == 2
+        ));
+    }
+}
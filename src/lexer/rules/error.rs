@@ -0,0 +1,30 @@
+use ruinous_util::{
+    error::{context::ErrorProvider, writer::ErrorWriter},
+    span::Span,
+};
+
+use crate::lexer::state::Shift;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RuleError {
+    UnexpectedChar(Span<char>),
+}
+
+impl RuleError {
+    pub(crate) fn shift(&mut self, shift: Shift) {
+        match self {
+            RuleError::UnexpectedChar(span) => *span = shift.span(*span),
+        }
+    }
+}
+
+impl ErrorProvider for RuleError {
+    fn write_errors(&self, writer: &mut dyn ErrorWriter) -> std::fmt::Result {
+        match self {
+            RuleError::UnexpectedChar(span) => writer.error(
+                Some(span.swap(())),
+                &format!("Unexpected character `{}`", span.data.escape_debug()),
+            ),
+        }
+    }
+}
@@ -0,0 +1,304 @@
+use std::{collections::VecDeque, sync::Arc};
+
+use ruinous_util::{
+    automaton::{dfa::Dfa, nfa::Nfa},
+    span::{Location, Span},
+};
+
+use crate::{
+    lexer::state::{Shift, Sink},
+    reader::Line,
+};
+
+use super::{Constructor, RuleError};
+
+pub(crate) trait Automaton {
+    type State: Clone + PartialEq;
+
+    fn start(&self) -> Self::State;
+    fn step(&self, state: &Self::State, char: char) -> Option<Self::State>;
+    fn accepts(&self, state: &Self::State) -> Option<usize>;
+    fn is_final(&self, state: &Self::State) -> bool;
+}
+
+pub(crate) struct Table<A, Token> {
+    pub(crate) automaton: A,
+    pub(crate) constructors: Vec<Option<Box<Constructor<Token>>>>,
+}
+
+pub(crate) struct Matcher<A: Automaton, Token> {
+    table: Arc<Table<A, Token>>,
+    current: Option<A::State>,
+    pending: Vec<Span<char>>,
+    accepted: Option<(usize, usize)>,
+    replay: VecDeque<Span<char>>,
+}
+
+impl<A: Automaton, Token> Matcher<A, Token> {
+    pub(crate) fn new(table: Table<A, Token>) -> Self {
+        Self {
+            table: Arc::new(table),
+            current: None,
+            pending: Vec::new(),
+            accepted: None,
+            replay: VecDeque::new(),
+        }
+    }
+
+    pub(crate) fn table(&self) -> &Table<A, Token> {
+        &self.table
+    }
+
+    pub(crate) fn pending(&self) -> Option<Location> {
+        self.pending
+            .first()
+            .or(self.replay.front())
+            .map(|char| char.start)
+    }
+
+    pub(crate) fn converges(&self, other: &Self) -> bool {
+        let chars = |matcher: &Self| {
+            matcher
+                .pending
+                .iter()
+                .chain(&matcher.replay)
+                .map(|char| char.data)
+                .collect::<Vec<_>>()
+        };
+
+        self.current == other.current
+            && self.accepted == other.accepted
+            && chars(self) == chars(other)
+    }
+
+    pub(crate) fn shift(&mut self, shift: Shift) {
+        for char in self.pending.iter_mut().chain(&mut self.replay) {
+            *char = shift.span(*char);
+        }
+    }
+
+    pub(crate) fn take_pending(&mut self) -> Vec<Span<char>> {
+        self.current = None;
+        self.accepted = None;
+        self.pending
+            .drain(..)
+            .chain(self.replay.drain(..))
+            .collect()
+    }
+
+    pub(crate) fn process<S: Sink<Token, RuleError> + ?Sized>(
+        &mut self,
+        input: Span<char>,
+        sink: &mut S,
+    ) {
+        self.step(input, sink);
+        self.drain(sink);
+    }
+
+    /// Match a whole line at once, slicing token text straight out of it. Anything a
+    /// token might still be matching at the end of the line goes through `process`.
+    pub(crate) fn process_line<S: Sink<Token, RuleError> + ?Sized>(
+        &mut self,
+        line: &Line<'_>,
+        sink: &mut S,
+    ) {
+        if self.current.is_some() || !self.pending.is_empty() || !self.replay.is_empty() {
+            for char in line.chars() {
+                self.process(char, sink);
+            }
+
+            return;
+        }
+
+        let table = self.table.clone();
+        let automaton = &table.automaton;
+        let chars = line.chars().collect::<Vec<_>>();
+
+        let indices = line
+            .text
+            .char_indices()
+            .map(|(index, _)| index)
+            .chain([line.text.len()])
+            .collect::<Vec<_>>();
+        let mut position = 0;
+
+        while position < chars.len() {
+            let mut state = automaton.start();
+            let mut accepted = None;
+            let mut end = position;
+
+            loop {
+                let Some(char) = chars.get(end) else {
+                    for &char in &chars[position..] {
+                        self.process(char, sink);
+                    }
+
+                    return;
+                };
+
+                let Some(next) = automaton.step(&state, char.data) else {
+                    break;
+                };
+
+                end += 1;
+
+                if let Some(rule) = automaton.accepts(&next) {
+                    accepted = Some((rule, end));
+
+                    if automaton.is_final(&next) {
+                        break;
+                    }
+                }
+
+                state = next;
+            }
+
+            match accepted {
+                Some((rule, end)) => {
+                    if let Some(constructor) = &table.constructors[rule] {
+                        let text = &line.text[indices[position]..indices[end]];
+                        let (start, end) = (chars[position].start, chars[end - 1].end);
+                        sink.token(Span::new(start, end, constructor(text)));
+                    }
+
+                    position = end;
+                }
+                None => {
+                    sink.error(RuleError::UnexpectedChar(chars[position]));
+                    position += 1;
+                }
+            }
+        }
+    }
+
+    pub(crate) fn finish<S: Sink<Token, RuleError> + ?Sized>(&mut self, sink: &mut S) {
+        while !self.pending.is_empty() {
+            self.flush(sink);
+            self.drain(sink);
+        }
+    }
+
+    fn step<S: Sink<Token, RuleError> + ?Sized>(&mut self, input: Span<char>, sink: &mut S) {
+        let automaton = &self.table.automaton;
+
+        let next = match &self.current {
+            Some(current) => automaton.step(current, input.data),
+            None => automaton.step(&automaton.start(), input.data),
+        };
+
+        if let Some(next) = next {
+            self.pending.push(input);
+
+            if let Some(rule) = automaton.accepts(&next) {
+                self.accepted = Some((rule, self.pending.len()));
+
+                if automaton.is_final(&next) {
+                    self.flush(sink);
+                    return;
+                }
+            }
+
+            self.current = Some(next);
+            return;
+        }
+
+        if self.pending.is_empty() {
+            sink.error(RuleError::UnexpectedChar(input));
+            return;
+        }
+
+        self.replay.push_front(input);
+        self.flush(sink);
+    }
+
+    fn flush<S: Sink<Token, RuleError> + ?Sized>(&mut self, sink: &mut S) {
+        let mut pending = std::mem::take(&mut self.pending);
+        self.current = None;
+
+        let rest = match self.accepted.take() {
+            Some((rule, length)) => {
+                let rest = pending.split_off(length);
+
+                if let Some(constructor) = &self.table.constructors[rule] {
+                    let text = pending.iter().map(|char| char.data).collect::<String>();
+                    let start = pending[0].start;
+                    let end = pending[length - 1].end;
+                    sink.token(Span::new(start, end, constructor(&text)));
+                }
+
+                rest
+            }
+            None => {
+                sink.error(RuleError::UnexpectedChar(pending[0]));
+                pending.split_off(1)
+            }
+        };
+
+        for char in rest.into_iter().rev() {
+            self.replay.push_front(char);
+        }
+    }
+
+    fn drain<S: Sink<Token, RuleError> + ?Sized>(&mut self, sink: &mut S) {
+        while !sink.interrupted() {
+            let Some(input) = self.replay.pop_front() else {
+                break;
+            };
+
+            self.step(input, sink);
+        }
+    }
+}
+
+impl<A: Automaton, Token> Clone for Matcher<A, Token> {
+    fn clone(&self) -> Self {
+        Self {
+            table: self.table.clone(),
+            current: self.current.clone(),
+            pending: self.pending.clone(),
+            accepted: self.accepted,
+            replay: self.replay.clone(),
+        }
+    }
+}
+
+impl Automaton for Nfa {
+    type State = Vec<usize>;
+
+    fn start(&self) -> Self::State {
+        self.start()
+    }
+
+    fn step(&self, state: &Self::State, char: char) -> Option<Self::State> {
+        let next = self.step_set(state, char);
+        (!next.is_empty()).then_some(next)
+    }
+
+    fn accepts(&self, state: &Self::State) -> Option<usize> {
+        self.best_match(state)
+    }
+
+    fn is_final(&self, state: &Self::State) -> bool {
+        self.is_final(state)
+    }
+}
+
+impl Automaton for Dfa {
+    type State = u32;
+
+    fn start(&self) -> Self::State {
+        self.start()
+    }
+
+    fn step(&self, state: &Self::State, char: char) -> Option<Self::State> {
+        self.step(*state, char)
+    }
+
+    fn accepts(&self, state: &Self::State) -> Option<usize> {
+        self.accepts(*state)
+    }
+
+    fn is_final(&self, state: &Self::State) -> bool {
+        self.is_final(*state)
+    }
+}
@@ -0,0 +1,448 @@
+use ruinous_util::{
+    automaton::{dfa::Dfa, nfa::Nfa, pattern::Pattern},
+    span::{Location, Span},
+};
+
+use crate::reader::Line;
+
+use super::state::{Checkpoint, Continuation, LineState, Shift, Sink, State};
+
+use self::matcher::{Matcher, Table};
+
+pub use ruinous_util::automaton::{
+    dfa::Tables as DfaTables,
+    pattern::{CharSet, PatternError, PatternErrorKind},
+};
+
+pub use self::error::RuleError;
+
+mod error;
+mod matcher;
+
+pub type Constructor<Token> = dyn Fn(&str) -> Token + Send + Sync;
+
+pub trait TokenRules: Sized {
+    fn rules() -> Rules<Self>;
+
+    fn lexer() -> RuleLexer<Self> {
+        Self::rules().build()
+    }
+
+    fn dfa() -> DfaLexer<Self> {
+        Self::rules().build_dfa()
+    }
+}
+
+pub struct Rule {
+    pattern: Pattern,
+    priority: i32,
+}
+
+pub struct Rules<Token> {
+    rules: Vec<(Rule, Option<Box<Constructor<Token>>>)>,
+}
+
+pub struct RuleLexer<Token> {
+    matcher: Matcher<Nfa, Token>,
+}
+
+pub struct DfaLexer<Token> {
+    matcher: Matcher<Dfa, Token>,
+}
+
+impl Rule {
+    pub fn literal(text: &str) -> Self {
+        Self {
+            pattern: Pattern::literal(text),
+            priority: 1,
+        }
+    }
+
+    pub fn class(set: CharSet) -> Self {
+        Self {
+            pattern: Pattern::Set(set),
+            priority: 0,
+        }
+    }
+
+    pub fn regex(pattern: &str) -> Result<Self, PatternError> {
+        Ok(Self {
+            pattern: Pattern::parse(pattern)?,
+            priority: 0,
+        })
+    }
+
+    #[must_use]
+    pub fn priority(self, priority: i32) -> Self {
+        Self { priority, ..self }
+    }
+}
+
+impl<Token> Default for Rules<Token> {
+    fn default() -> Self {
+        Self { rules: Vec::new() }
+    }
+}
+
+impl<Token> Rules<Token> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[must_use]
+    pub fn rule<F: Fn(&str) -> Token + Send + Sync + 'static>(
+        mut self,
+        rule: Rule,
+        constructor: F,
+    ) -> Self {
+        self.rules.push((rule, Some(Box::new(constructor))));
+        self
+    }
+
+    #[must_use]
+    pub fn skip_rule(mut self, rule: Rule) -> Self {
+        self.rules.push((rule, None));
+        self
+    }
+
+    #[must_use]
+    pub fn literal<F: Fn(&str) -> Token + Send + Sync + 'static>(
+        self,
+        text: &str,
+        constructor: F,
+    ) -> Self {
+        self.rule(Rule::literal(text), constructor)
+    }
+
+    #[must_use]
+    pub fn class<F: Fn(&str) -> Token + Send + Sync + 'static>(
+        self,
+        set: CharSet,
+        constructor: F,
+    ) -> Self {
+        self.rule(Rule::class(set), constructor)
+    }
+
+    pub fn regex<F: Fn(&str) -> Token + Send + Sync + 'static>(
+        self,
+        pattern: &str,
+        constructor: F,
+    ) -> Result<Self, PatternError> {
+        Ok(self.rule(Rule::regex(pattern)?, constructor))
+    }
+
+    pub fn skip(self, pattern: &str) -> Result<Self, PatternError> {
+        Ok(self.skip_rule(Rule::regex(pattern)?))
+    }
+
+    pub fn build(self) -> RuleLexer<Token> {
+        let (nfa, constructors) = self.compile();
+
+        RuleLexer {
+            matcher: Matcher::new(Table {
+                automaton: nfa,
+                constructors,
+            }),
+        }
+    }
+
+    pub fn build_dfa(self) -> DfaLexer<Token> {
+        let (nfa, constructors) = self.compile();
+
+        DfaLexer {
+            matcher: Matcher::new(Table {
+                automaton: Dfa::new(&nfa),
+                constructors,
+            }),
+        }
+    }
+
+    /// Build a DFA lexer from tables generated ahead of time for these same rules, in
+    /// the same order, as `#[derive(Lexer)]` does.
+    pub fn build_dfa_from(self, tables: DfaTables<'static>) -> DfaLexer<Token> {
+        let constructors = self
+            .rules
+            .into_iter()
+            .map(|(_, constructor)| constructor)
+            .collect();
+
+        DfaLexer {
+            matcher: Matcher::new(Table {
+                automaton: Dfa::from_tables(tables),
+                constructors,
+            }),
+        }
+    }
+
+    fn compile(self) -> (Nfa, Vec<Option<Box<Constructor<Token>>>>) {
+        let nfa = Nfa::new(
+            self.rules
+                .iter()
+                .map(|(rule, _)| (&rule.pattern, rule.priority)),
+        );
+
+        let constructors = self
+            .rules
+            .into_iter()
+            .map(|(_, constructor)| constructor)
+            .collect();
+
+        (nfa, constructors)
+    }
+}
+
+impl<Token> DfaLexer<Token> {
+    pub fn states(&self) -> usize {
+        self.matcher.table().automaton.states()
+    }
+}
+
+impl<Token> Clone for RuleLexer<Token> {
+    fn clone(&self) -> Self {
+        Self {
+            matcher: self.matcher.clone(),
+        }
+    }
+}
+
+impl<Token> Clone for DfaLexer<Token> {
+    fn clone(&self) -> Self {
+        Self {
+            matcher: self.matcher.clone(),
+        }
+    }
+}
+
+impl<Token> State for RuleLexer<Token> {
+    type Token = Token;
+    type Error = RuleError;
+
+    fn process<S: Sink<Self::Token, Self::Error> + ?Sized>(
+        &mut self,
+        input: Span<char>,
+        sink: &mut S,
+    ) -> Continuation {
+        self.matcher.process(input, sink);
+        Continuation::Consume
+    }
+
+    fn finish<S: Sink<Self::Token, Self::Error> + ?Sized>(mut self, sink: &mut S) {
+        self.matcher.finish(sink);
+    }
+
+    fn take_pending(&mut self) -> Vec<Span<char>> {
+        self.matcher.take_pending()
+    }
+}
+
+impl<Token> State for DfaLexer<Token> {
+    type Token = Token;
+    type Error = RuleError;
+
+    fn process<S: Sink<Self::Token, Self::Error> + ?Sized>(
+        &mut self,
+        input: Span<char>,
+        sink: &mut S,
+    ) -> Continuation {
+        self.matcher.process(input, sink);
+        Continuation::Consume
+    }
+
+    fn finish<S: Sink<Self::Token, Self::Error> + ?Sized>(mut self, sink: &mut S) {
+        self.matcher.finish(sink);
+    }
+
+    fn take_pending(&mut self) -> Vec<Span<char>> {
+        self.matcher.take_pending()
+    }
+}
+
+impl<Token> Checkpoint for RuleLexer<Token> {
+    fn pending(&self) -> Option<Location> {
+        self.matcher.pending()
+    }
+
+    fn converges(&self, other: &Self) -> bool {
+        self.matcher.converges(&other.matcher)
+    }
+
+    fn shift(&mut self, shift: Shift) {
+        self.matcher.shift(shift);
+    }
+
+    fn shift_error(error: &mut Self::Error, shift: Shift) {
+        error.shift(shift);
+    }
+}
+
+impl<Token> Checkpoint for DfaLexer<Token> {
+    fn pending(&self) -> Option<Location> {
+        self.matcher.pending()
+    }
+
+    fn converges(&self, other: &Self) -> bool {
+        self.matcher.converges(&other.matcher)
+    }
+
+    fn shift(&mut self, shift: Shift) {
+        self.matcher.shift(shift);
+    }
+
+    fn shift_error(error: &mut Self::Error, shift: Shift) {
+        error.shift(shift);
+    }
+}
+
+impl<Token> LineState for DfaLexer<Token> {
+    fn process_line<S: Sink<Self::Token, Self::Error> + ?Sized>(
+        &mut self,
+        line: Line<'_>,
+        sink: &mut S,
+    ) {
+        self.matcher.process_line(&line, sink);
+        self.matcher.process(line.newline, sink);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::lexer::{Error, Lexer};
+
+    #[derive(Debug, Clone, PartialEq)]
+    enum Token {
+        If,
+        Ident(String),
+        Number(u64),
+        Float(String),
+        Arrow,
+        Minus,
+        Greater,
+    }
+
+    fn rules() -> Rules<Token> {
+        Rules::new()
+            .literal("if", |_| Token::If)
+            .literal("->", |_| Token::Arrow)
+            .literal("-", |_| Token::Minus)
+            .class(CharSet::single('>'), |_| Token::Greater)
+            .regex("[a-zA-Z_][a-zA-Z0-9_]*", |text| {
+                Token::Ident(text.to_owned())
+            })
+            .and_then(|rules| rules.regex(r"\d+", |text| Token::Number(text.parse().unwrap())))
+            .and_then(|rules| {
+                rules.regex(r"\d+\.\d+([eE][+-]?\d+)?", |text| {
+                    Token::Float(text.to_owned())
+                })
+            })
+            .and_then(|rules| rules.skip(r"\s+"))
+            .unwrap()
+    }
+
+    fn errors<S: State<Error = RuleError>>(result: Result<(), Error<S>>) -> Vec<RuleError> {
+        match result {
+            Ok(()) => Vec::new(),
+            Err(Error::LexErrors(errors)) => errors.errors,
+            Err(error) => panic!("{error:?}"),
+        }
+    }
+
+    fn lex(input: &str) -> (Vec<Token>, Vec<RuleError>) {
+        let mut tokens = Vec::new();
+        let result = Lexer::with_str(input).lex(rules().build(), |token| tokens.push(token));
+        let errors = errors(result);
+
+        let mut dfa_tokens = Vec::new();
+        let result = Lexer::with_str(input).lex_lines(rules().build_dfa(), |token| {
+            dfa_tokens.push(token);
+        });
+
+        assert_eq!(tokens, dfa_tokens);
+        assert_eq!(errors, self::errors(result));
+
+        (tokens.into_iter().map(|token| token.data).collect(), errors)
+    }
+
+    #[test]
+    fn rules_minimise_dfa() {
+        let lexer = Rules::new()
+            .regex("ab|cb", |_| Token::If)
+            .and_then(|rules| rules.regex("x+|y+", |_| Token::Minus))
+            .unwrap()
+            .build_dfa();
+
+        assert_eq!(lexer.states(), 6);
+    }
+
+    #[test]
+    fn rules_longest_match() {
+        let (tokens, errors) = lex("iffy if -> - > 12 1.5e3 1e");
+
+        assert!(errors.is_empty());
+        assert_eq!(
+            tokens,
+            [
+                Token::Ident("iffy".to_owned()),
+                Token::If,
+                Token::Arrow,
+                Token::Minus,
+                Token::Greater,
+                Token::Number(12),
+                Token::Float("1.5e3".to_owned()),
+                Token::Number(1),
+                Token::Ident("e".to_owned()),
+            ]
+        );
+    }
+
+    #[test]
+    fn rules_priority_breaks_ties() {
+        let mut tokens = Vec::new();
+
+        let lexer = Rules::new()
+            .regex("[a-z]+", |text| Token::Ident(text.to_owned()))
+            .unwrap()
+            .rule(Rule::regex("i[a-z]").unwrap().priority(2), |_| Token::If)
+            .skip_rule(Rule::class(CharSet::whitespace()))
+            .build();
+
+        Lexer::with_str("if iff")
+            .lex(lexer, |token| tokens.push(token.data))
+            .unwrap();
+
+        assert_eq!(tokens, [Token::If, Token::Ident("iff".to_owned())]);
+    }
+
+    #[test]
+    fn rules_recover_from_unexpected_chars() {
+        let (tokens, errors) = lex("a $ b 1.");
+
+        assert_eq!(
+            tokens,
+            [
+                Token::Ident("a".to_owned()),
+                Token::Ident("b".to_owned()),
+                Token::Number(1),
+            ]
+        );
+
+        let chars = errors
+            .iter()
+            .map(|RuleError::UnexpectedChar(span)| (span.data, span.start.col))
+            .collect::<Vec<_>>();
+
+        assert_eq!(chars, [('$', 2), ('.', 7)]);
+    }
+
+    #[test]
+    fn rules_reject_invalid_patterns() {
+        let error = Rule::regex("a(b").err().unwrap();
+        assert_eq!(error.kind, PatternErrorKind::UnclosedGroup);
+
+        let error = Rule::regex("[z-a]").err().unwrap();
+        assert_eq!(error.kind, PatternErrorKind::InvalidRange);
+
+        let error = Rule::regex("*a").err().unwrap();
+        assert_eq!(error.kind, PatternErrorKind::NothingToRepeat);
+    }
+}
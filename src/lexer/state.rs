@@ -1,16 +1,112 @@
-use ruinous_util::{error::context::ErrorProvider, span::Span};
+use std::collections::VecDeque;
 
-pub use crate::reader::Continuation;
+use ruinous_util::{
+    error::context::ErrorProvider,
+    span::{Location, Span},
+};
+
+pub use crate::reader::{Continuation, Line};
+
+pub trait Sink<Token, Error> {
+    fn token(&mut self, token: Span<Token>);
+    fn error(&mut self, error: Error);
+
+    /// Whether the state should stop and hold on to the rest of its input.
+    fn interrupted(&self) -> bool {
+        false
+    }
+}
 
 pub trait State {
     type Token;
     type Error: ErrorProvider;
 
-    fn process<Callback: FnMut(Span<Self::Token>)>(
+    fn process<S: Sink<Self::Token, Self::Error> + ?Sized>(
         &mut self,
         input: Span<char>,
-        callback: &mut Callback,
+        sink: &mut S,
     ) -> Continuation;
 
-    fn finish(self) -> Result<(), Self::Error>;
+    fn finish<S: Sink<Self::Token, Self::Error> + ?Sized>(self, sink: &mut S);
+
+    /// Hand back any input that has been taken in but not lexed yet. A state that
+    /// buffers input needs to implement this and stop once [`Sink::interrupted`] is
+    /// set, or a [`Modal`](super::modal::Modal) lexes the buffered input in the old mode.
+    fn take_pending(&mut self) -> Vec<Span<char>> {
+        Vec::new()
+    }
+}
+
+pub trait LineState: State {
+    fn process_line<S: Sink<Self::Token, Self::Error> + ?Sized>(
+        &mut self,
+        line: Line<'_>,
+        sink: &mut S,
+    );
+}
+
+/// A state that can be cloned part way through its input and resumed later.
+pub trait Checkpoint: State + Clone {
+    /// The start of the earliest input this state is still holding on to, if any.
+    fn pending(&self) -> Option<Location>;
+
+    /// Whether this state will lex any further input exactly as `other` would, ignoring
+    /// where their pending input is located.
+    fn converges(&self, other: &Self) -> bool;
+
+    fn shift(&mut self, shift: Shift);
+
+    fn shift_error(error: &mut Self::Error, shift: Shift);
+}
+
+/// Maps locations after an edited region of text to where they are after the edit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Shift {
+    pub from: Location,
+    pub to: Location,
+}
+
+impl Shift {
+    pub fn new(from: Location, to: Location) -> Self {
+        Self { from, to }
+    }
+
+    pub fn apply(self, location: Location) -> Location {
+        let col = if location.line == self.from.line {
+            (location.col + self.to.col).saturating_sub(self.from.col)
+        } else {
+            location.col
+        };
+
+        Location {
+            line: (location.line + self.to.line).saturating_sub(self.from.line),
+            col,
+            offset: (location.offset + self.to.offset).saturating_sub(self.from.offset),
+            ..location
+        }
+    }
+
+    pub fn span<Data>(self, span: Span<Data>) -> Span<Data> {
+        Span::new(self.apply(span.start), self.apply(span.end), span.data)
+    }
+}
+
+impl<Token, Error> Sink<Token, Error> for Vec<Result<Span<Token>, Error>> {
+    fn token(&mut self, token: Span<Token>) {
+        self.push(Ok(token));
+    }
+
+    fn error(&mut self, error: Error) {
+        self.push(Err(error));
+    }
+}
+
+impl<Token, Error> Sink<Token, Error> for VecDeque<Result<Span<Token>, Error>> {
+    fn token(&mut self, token: Span<Token>) {
+        self.push_back(Ok(token));
+    }
+
+    fn error(&mut self, error: Error) {
+        self.push_back(Err(error));
+    }
 }
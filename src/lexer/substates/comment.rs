@@ -0,0 +1,166 @@
+use ruinous_util::span::{Location, Span};
+
+use super::{ScanError, Step, SubState};
+
+#[derive(Clone)]
+pub struct LineComment {
+    prefix: String,
+    active: Option<Span<String>>,
+}
+
+#[derive(Clone)]
+pub struct BlockComment {
+    open: String,
+    close: String,
+    nested: bool,
+    active: Option<Block>,
+}
+
+#[derive(Clone)]
+struct Block {
+    start: Location,
+    end: Location,
+    text: String,
+    depth: usize,
+    matched_from: usize,
+}
+
+impl LineComment {
+    pub fn new<S: Into<String>>(prefix: S) -> Self {
+        Self {
+            prefix: prefix.into(),
+            active: None,
+        }
+    }
+}
+
+impl BlockComment {
+    pub fn new<O: Into<String>, C: Into<String>>(open: O, close: C) -> Self {
+        Self {
+            open: open.into(),
+            close: close.into(),
+            nested: false,
+            active: None,
+        }
+    }
+
+    #[must_use]
+    pub fn nested(self, nested: bool) -> Self {
+        Self { nested, ..self }
+    }
+}
+
+impl SubState for LineComment {
+    type Output = String;
+
+    fn accepts(&self, first: char) -> bool {
+        self.prefix.starts_with(first)
+    }
+
+    fn process(&mut self, input: Span<char>, errors: &mut Vec<ScanError>) -> Step<Self::Output> {
+        let active = self
+            .active
+            .get_or_insert(Span::new(input.start, input.start, String::new()));
+
+        if active.data.len() < self.prefix.len() {
+            active.data.push(input.data);
+
+            if !self.prefix.starts_with(&active.data) {
+                self.active = None;
+                return Step::Reject;
+            }
+        } else if input.data == '\n' {
+            return self.finish(errors);
+        } else {
+            active.data.push(input.data);
+        }
+
+        active.end = input.end;
+        Step::Continue
+    }
+
+    fn finish(&mut self, _: &mut Vec<ScanError>) -> Step<Self::Output> {
+        match self.active.take() {
+            Some(active) if active.data.len() >= self.prefix.len() => {
+                let prefix = self.prefix.len();
+                Step::Done(active.map(|text| text[prefix..].to_owned()))
+            }
+            _ => Step::Reject,
+        }
+    }
+}
+
+impl SubState for BlockComment {
+    type Output = String;
+
+    fn accepts(&self, first: char) -> bool {
+        self.open.starts_with(first)
+    }
+
+    fn process(&mut self, input: Span<char>, errors: &mut Vec<ScanError>) -> Step<Self::Output> {
+        let active = self.active.get_or_insert(Block {
+            start: input.start,
+            end: input.start,
+            text: String::new(),
+            depth: 0,
+            matched_from: 0,
+        });
+
+        active.text.push(input.data);
+        active.end = input.end;
+
+        if active.depth == 0 {
+            if active.text == self.open {
+                active.depth = 1;
+                active.matched_from = active.text.len();
+            } else if !self.open.starts_with(&active.text) {
+                self.active = None;
+                return Step::Reject;
+            }
+
+            return Step::Continue;
+        }
+
+        let unmatched = &active.text[active.matched_from..];
+
+        if unmatched.ends_with(&self.close) {
+            active.depth -= 1;
+            active.matched_from = active.text.len();
+
+            if active.depth == 0 {
+                return self.finish(errors);
+            }
+        } else if self.nested && unmatched.ends_with(&self.open) {
+            active.depth += 1;
+            active.matched_from = active.text.len();
+        }
+
+        Step::Continue
+    }
+
+    fn finish(&mut self, errors: &mut Vec<ScanError>) -> Step<Self::Output> {
+        let Some(active) = self.active.take() else {
+            return Step::Reject;
+        };
+
+        if active.depth > 0 {
+            errors.push(ScanError::UnterminatedComment(Span::new(
+                active.start,
+                active.end,
+                (),
+            )));
+        } else if active.text.len() < self.open.len() {
+            return Step::Reject;
+        }
+
+        let open = self.open.len();
+        let close = if active.depth == 0 {
+            self.close.len()
+        } else {
+            0
+        };
+        let text = active.text[open..active.text.len() - close].to_owned();
+
+        Step::Done(Span::new(active.start, active.end, text))
+    }
+}
@@ -0,0 +1,61 @@
+use ruinous_util::{
+    error::{context::ErrorProvider, writer::ErrorWriter},
+    span::Span,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ScanError {
+    UnexpectedChar(Span<char>),
+    UnterminatedString(Span<()>),
+    UnterminatedChar(Span<()>),
+    EmptyChar(Span<()>),
+    OverlongChar(Span<()>),
+    BadEscape(Span<String>),
+    UnterminatedComment(Span<()>),
+    MissingDigits(Span<()>),
+    InvalidDigit(Span<char>),
+    MissingExponent(Span<()>),
+    IntegerOverflow(Span<()>),
+}
+
+impl ErrorProvider for ScanError {
+    fn write_errors(&self, writer: &mut dyn ErrorWriter) -> std::fmt::Result {
+        match self {
+            ScanError::UnexpectedChar(span) => writer.error(
+                Some(span.swap(())),
+                &format!("Unexpected character `{}`", span.data.escape_debug()),
+            ),
+            ScanError::UnterminatedString(span) => {
+                writer.error(Some(*span), "Unterminated string literal")
+            }
+            ScanError::UnterminatedChar(span) => {
+                writer.error(Some(*span), "Unterminated character literal")
+            }
+            ScanError::EmptyChar(span) => writer.error(Some(*span), "Empty character literal"),
+            ScanError::OverlongChar(span) => writer.error(
+                Some(*span),
+                "Character literal may only contain one character",
+            ),
+            ScanError::BadEscape(span) => writer.error(
+                Some(span.swap(())),
+                &format!("Unknown escape sequence `{}`", span.data.escape_debug()),
+            ),
+            ScanError::UnterminatedComment(span) => {
+                writer.error(Some(*span), "Unterminated block comment")
+            }
+            ScanError::MissingDigits(span) => {
+                writer.error(Some(*span), "Missing digits after integer prefix")
+            }
+            ScanError::InvalidDigit(span) => writer.error(
+                Some(span.swap(())),
+                &format!("Invalid digit `{}` for this base", span.data),
+            ),
+            ScanError::MissingExponent(span) => {
+                writer.error(Some(*span), "Missing digits in float exponent")
+            }
+            ScanError::IntegerOverflow(span) => {
+                writer.error(Some(*span), "Integer literal is too large")
+            }
+        }
+    }
+}
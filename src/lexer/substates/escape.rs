@@ -0,0 +1,105 @@
+use ruinous_util::span::{Location, Span};
+
+use super::ScanError;
+
+#[derive(Default, Clone)]
+pub(crate) struct Escape {
+    state: EscapeState,
+    raw: String,
+}
+
+#[derive(Default, Clone, Copy)]
+enum EscapeState {
+    #[default]
+    None,
+    Backslash(Location),
+    Hex(Location, u32, usize),
+    UnicodeOpen(Location),
+    Unicode(Location, u32, usize),
+}
+
+pub(crate) enum EscapeStep {
+    Literal,
+    Pending,
+    Char(char),
+    Invalid { consumed: bool },
+}
+
+impl Escape {
+    pub(crate) fn process(&mut self, input: Span<char>, errors: &mut Vec<ScanError>) -> EscapeStep {
+        let char = input.data;
+
+        let (next, step) = match self.state {
+            EscapeState::None if char == '\\' => {
+                self.raw.clear();
+                (EscapeState::Backslash(input.start), EscapeStep::Pending)
+            }
+            EscapeState::None => (EscapeState::None, EscapeStep::Literal),
+            EscapeState::Backslash(start) => match char {
+                'n' => (EscapeState::None, EscapeStep::Char('\n')),
+                'r' => (EscapeState::None, EscapeStep::Char('\r')),
+                't' => (EscapeState::None, EscapeStep::Char('\t')),
+                '0' => (EscapeState::None, EscapeStep::Char('\0')),
+                '\\' | '\'' | '"' => (EscapeState::None, EscapeStep::Char(char)),
+                'x' => (EscapeState::Hex(start, 0, 0), EscapeStep::Pending),
+                'u' => (EscapeState::UnicodeOpen(start), EscapeStep::Pending),
+                '\n' => self.invalid(start, input, false, errors),
+                _ => self.invalid(start, input, true, errors),
+            },
+            EscapeState::Hex(start, value, digits) => match char.to_digit(16) {
+                Some(digit) if digits == 1 => match char::from_u32(value * 16 + digit) {
+                    Some(char) if char.is_ascii() => (EscapeState::None, EscapeStep::Char(char)),
+                    _ => self.invalid(start, input, true, errors),
+                },
+                Some(digit) => (EscapeState::Hex(start, digit, 1), EscapeStep::Pending),
+                None => self.invalid(start, input, false, errors),
+            },
+            EscapeState::UnicodeOpen(start) if char == '{' => {
+                (EscapeState::Unicode(start, 0, 0), EscapeStep::Pending)
+            }
+            EscapeState::UnicodeOpen(start) => self.invalid(start, input, false, errors),
+            EscapeState::Unicode(start, value, digits) => match (char, char.to_digit(16)) {
+                ('}', _) if digits > 0 => match char::from_u32(value) {
+                    Some(char) => (EscapeState::None, EscapeStep::Char(char)),
+                    None => self.invalid(start, input, true, errors),
+                },
+                (_, Some(digit)) if digits < 6 => (
+                    EscapeState::Unicode(start, value * 16 + digit, digits + 1),
+                    EscapeStep::Pending,
+                ),
+                _ => self.invalid(start, input, false, errors),
+            },
+        };
+
+        if let EscapeState::None = next {
+            self.raw.clear();
+        } else {
+            self.raw.push(char);
+        }
+
+        self.state = next;
+        step
+    }
+
+    fn invalid(
+        &mut self,
+        start: Location,
+        input: Span<char>,
+        consumed: bool,
+        errors: &mut Vec<ScanError>,
+    ) -> (EscapeState, EscapeStep) {
+        let end = if consumed {
+            self.raw.push(input.data);
+            input.end
+        } else {
+            input.start
+        };
+
+        errors.push(ScanError::BadEscape(Span::new(
+            start,
+            end,
+            self.raw.clone(),
+        )));
+        (EscapeState::None, EscapeStep::Invalid { consumed })
+    }
+}
@@ -0,0 +1,63 @@
+use ruinous_util::span::Span;
+
+use super::{ScanError, Step, SubState};
+
+#[derive(Clone)]
+pub struct Identifier {
+    start: fn(char) -> bool,
+    continues: fn(char) -> bool,
+    active: Option<Span<String>>,
+}
+
+impl Default for Identifier {
+    fn default() -> Self {
+        Self {
+            start: |char| char.is_alphabetic() || char == '_',
+            continues: |char| char.is_alphanumeric() || char == '_',
+            active: None,
+        }
+    }
+}
+
+impl Identifier {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[must_use]
+    pub fn start(self, start: fn(char) -> bool) -> Self {
+        Self { start, ..self }
+    }
+
+    #[must_use]
+    pub fn continues(self, continues: fn(char) -> bool) -> Self {
+        Self { continues, ..self }
+    }
+}
+
+impl SubState for Identifier {
+    type Output = String;
+
+    fn accepts(&self, first: char) -> bool {
+        (self.start)(first)
+    }
+
+    fn process(&mut self, input: Span<char>, errors: &mut Vec<ScanError>) -> Step<Self::Output> {
+        match &mut self.active {
+            None => {
+                self.active = Some(input.map(String::from));
+                Step::Continue
+            }
+            Some(active) if (self.continues)(input.data) => {
+                active.data.push(input.data);
+                active.end = input.end;
+                Step::Continue
+            }
+            Some(_) => self.finish(errors),
+        }
+    }
+
+    fn finish(&mut self, _: &mut Vec<ScanError>) -> Step<Self::Output> {
+        self.active.take().map_or(Step::Reject, Step::Done)
+    }
+}
@@ -0,0 +1,400 @@
+use std::collections::VecDeque;
+
+use ruinous_util::span::{Location, Span};
+
+use super::state::{Continuation, Sink, State};
+
+pub use self::{
+    comment::{BlockComment, LineComment},
+    error::ScanError,
+    identifier::Identifier,
+    number::{Number, NumberLiteral},
+    string::{CharLiteral, StringLiteral},
+    symbols::Symbols,
+};
+
+mod comment;
+mod error;
+mod escape;
+mod identifier;
+mod number;
+mod string;
+mod symbols;
+
+pub enum Step<Output> {
+    Continue,
+    Done(Span<Output>),
+    Reject,
+}
+
+pub trait SubState {
+    type Output;
+
+    fn accepts(&self, first: char) -> bool;
+    fn process(&mut self, input: Span<char>, errors: &mut Vec<ScanError>) -> Step<Self::Output>;
+
+    /// Called at the end of input, or when a token is abandoned part way through, and
+    /// leaves the state ready to start a new token.
+    fn finish(&mut self, errors: &mut Vec<ScanError>) -> Step<Self::Output>;
+}
+
+pub struct SubStates<Token> {
+    entries: Vec<Box<dyn Entry<Token>>>,
+    skip: fn(char) -> bool,
+    active: Option<usize>,
+    retry: Option<(Location, usize)>,
+    buffer: Vec<Span<char>>,
+    replay: VecDeque<Span<char>>,
+    errors: Vec<ScanError>,
+}
+
+trait Entry<Token> {
+    fn accepts(&self, first: char) -> bool;
+    fn process(&mut self, input: Span<char>, errors: &mut Vec<ScanError>) -> Step<Option<Token>>;
+    fn finish(&mut self, errors: &mut Vec<ScanError>) -> Step<Option<Token>>;
+}
+
+struct Mapped<S, F> {
+    state: S,
+    map: F,
+}
+
+impl<Output> Step<Output> {
+    pub fn map<New, F: FnOnce(Output) -> New>(self, f: F) -> Step<New> {
+        match self {
+            Step::Continue => Step::Continue,
+            Step::Done(span) => Step::Done(span.map(f)),
+            Step::Reject => Step::Reject,
+        }
+    }
+}
+
+impl<S: SubState, Token, F: Fn(S::Output) -> Option<Token>> Entry<Token> for Mapped<S, F> {
+    fn accepts(&self, first: char) -> bool {
+        self.state.accepts(first)
+    }
+
+    fn process(&mut self, input: Span<char>, errors: &mut Vec<ScanError>) -> Step<Option<Token>> {
+        self.state.process(input, errors).map(&self.map)
+    }
+
+    fn finish(&mut self, errors: &mut Vec<ScanError>) -> Step<Option<Token>> {
+        self.state.finish(errors).map(&self.map)
+    }
+}
+
+impl<Token> Default for SubStates<Token> {
+    fn default() -> Self {
+        Self {
+            entries: Vec::new(),
+            skip: char::is_whitespace,
+            active: None,
+            retry: None,
+            buffer: Vec::new(),
+            replay: VecDeque::new(),
+            errors: Vec::new(),
+        }
+    }
+}
+
+impl<Token> SubStates<Token> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[must_use]
+    pub fn with<S, F>(mut self, state: S, map: F) -> Self
+    where
+        S: SubState + 'static,
+        F: Fn(S::Output) -> Token + 'static,
+        Token: 'static,
+    {
+        self.entries.push(Box::new(Mapped {
+            state,
+            map: move |output| Some(map(output)),
+        }));
+        self
+    }
+
+    #[must_use]
+    pub fn skip<S: SubState + 'static>(mut self, state: S) -> Self
+    where
+        Token: 'static,
+    {
+        self.entries.push(Box::new(Mapped {
+            state,
+            map: |_| None,
+        }));
+        self
+    }
+
+    #[must_use]
+    pub fn skip_chars(self, skip: fn(char) -> bool) -> Self {
+        Self { skip, ..self }
+    }
+
+    fn step<S: Sink<Token, ScanError> + ?Sized>(&mut self, input: Span<char>, sink: &mut S) {
+        let active = match self.active {
+            Some(active) => active,
+            None => {
+                let first = match self.retry.take() {
+                    Some((location, first)) if location == input.start => first,
+                    _ => 0,
+                };
+
+                let found = self.entries[first.min(self.entries.len())..]
+                    .iter()
+                    .position(|entry| entry.accepts(input.data));
+
+                match found {
+                    Some(index) => first + index,
+                    None if (self.skip)(input.data) => return,
+                    None => {
+                        sink.error(ScanError::UnexpectedChar(input));
+                        return;
+                    }
+                }
+            }
+        };
+
+        self.active = Some(active);
+        self.buffer.push(input);
+
+        let step = self.entries[active].process(input, &mut self.errors);
+        self.complete(active, step, sink);
+    }
+
+    fn complete<S: Sink<Token, ScanError> + ?Sized>(
+        &mut self,
+        active: usize,
+        step: Step<Option<Token>>,
+        sink: &mut S,
+    ) {
+        for error in self.errors.drain(..) {
+            sink.error(error);
+        }
+
+        let rest = match step {
+            Step::Continue => return,
+            Step::Done(token) => {
+                let end = token.end;
+
+                if let Some(data) = token.data {
+                    sink.token(Span::new(token.start, token.end, data));
+                }
+
+                let mut buffer = std::mem::take(&mut self.buffer);
+                buffer.retain(|char| char.start >= end);
+                buffer
+            }
+            Step::Reject => {
+                let buffer = std::mem::take(&mut self.buffer);
+                self.retry = buffer.first().map(|first| (first.start, active + 1));
+                buffer
+            }
+        };
+
+        self.active = None;
+
+        for char in rest.into_iter().rev() {
+            self.replay.push_front(char);
+        }
+    }
+
+    fn drain<S: Sink<Token, ScanError> + ?Sized>(&mut self, sink: &mut S) {
+        while !sink.interrupted() {
+            let Some(input) = self.replay.pop_front() else {
+                break;
+            };
+
+            self.step(input, sink);
+        }
+    }
+}
+
+impl<Token> State for SubStates<Token> {
+    type Token = Token;
+    type Error = ScanError;
+
+    fn process<S: Sink<Self::Token, Self::Error> + ?Sized>(
+        &mut self,
+        input: Span<char>,
+        sink: &mut S,
+    ) -> Continuation {
+        self.step(input, sink);
+        self.drain(sink);
+        Continuation::Consume
+    }
+
+    fn finish<S: Sink<Self::Token, Self::Error> + ?Sized>(mut self, sink: &mut S) {
+        while let Some(active) = self.active {
+            let step = match self.entries[active].finish(&mut self.errors) {
+                Step::Continue => Step::Reject,
+                step => step,
+            };
+
+            self.complete(active, step, sink);
+            self.drain(sink);
+        }
+    }
+
+    fn take_pending(&mut self) -> Vec<Span<char>> {
+        if let Some(active) = self.active.take() {
+            self.entries[active].finish(&mut Vec::new());
+        }
+
+        self.retry = None;
+        self.buffer.drain(..).chain(self.replay.drain(..)).collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::lexer::{Error, Lexer};
+
+    #[derive(Debug, Clone, PartialEq)]
+    enum Token {
+        Ident(String),
+        Number(NumberLiteral),
+        String(String),
+        Char(char),
+        Comment(String),
+        Slash,
+        Dot,
+        DotDot,
+    }
+
+    fn lex(input: &str) -> (Vec<Span<Token>>, Vec<ScanError>) {
+        let state = SubStates::new()
+            .with(Identifier::new(), Token::Ident)
+            .with(Number::new(), Token::Number)
+            .with(StringLiteral::new(), Token::String)
+            .with(CharLiteral::new(), Token::Char)
+            .with(LineComment::new("//"), Token::Comment)
+            .skip(BlockComment::new("/*", "*/").nested(true))
+            .with(
+                Symbols::new([
+                    ("/", Token::Slash),
+                    (".", Token::Dot),
+                    ("..", Token::DotDot),
+                ]),
+                |token| token,
+            );
+
+        let mut tokens = Vec::new();
+
+        let errors = match Lexer::with_str(input).lex(state, |token| tokens.push(token)) {
+            Ok(()) => Vec::new(),
+            Err(Error::LexErrors(errors)) => errors.errors,
+            Err(error) => panic!("{error:?}"),
+        };
+
+        (tokens, errors)
+    }
+
+    fn data(tokens: Vec<Span<Token>>) -> Vec<Token> {
+        tokens.into_iter().map(|token| token.data).collect()
+    }
+
+    fn integer(value: u128, radix: u32) -> Token {
+        Token::Number(NumberLiteral::Integer { value, radix })
+    }
+
+    #[test]
+    fn substates_numbers() {
+        let (tokens, errors) = lex("12 0x1F 0b1_01 0o7 1.5 2e3 1..2 3.x");
+
+        assert!(errors.is_empty(), "{errors:?}");
+        assert_eq!(
+            data(tokens),
+            [
+                integer(12, 10),
+                integer(31, 16),
+                integer(5, 2),
+                integer(7, 8),
+                Token::Number(NumberLiteral::Float(1.5)),
+                Token::Number(NumberLiteral::Float(2000.0)),
+                integer(1, 10),
+                Token::DotDot,
+                integer(2, 10),
+                integer(3, 10),
+                Token::Dot,
+                Token::Ident("x".to_owned()),
+            ]
+        );
+    }
+
+    #[test]
+    fn substates_number_errors() {
+        let (_, errors) = lex("0x 0b102 1e+ 999999999999999999999999999999999999999999");
+
+        assert!(matches!(errors[0], ScanError::MissingDigits(_)));
+        assert!(matches!(
+            errors[1],
+            ScanError::InvalidDigit(Span { data: '2', .. })
+        ));
+        assert!(matches!(errors[2], ScanError::MissingExponent(_)));
+        assert!(matches!(errors[3], ScanError::IntegerOverflow(_)));
+        assert_eq!(errors.len(), 4);
+    }
+
+    #[test]
+    fn substates_strings_and_chars() {
+        let (tokens, errors) = lex(r#""a\tb\u{1F409}\x41" 'c' '\n'"#);
+
+        assert!(errors.is_empty(), "{errors:?}");
+        assert_eq!(
+            data(tokens),
+            [
+                Token::String("a\tb🐉A".to_owned()),
+                Token::Char('c'),
+                Token::Char('\n'),
+            ]
+        );
+    }
+
+    #[test]
+    fn substates_string_errors() {
+        let (tokens, errors) = lex("\"bad \\q escape\" \"open\nnext '' 'ab'");
+
+        assert_eq!(
+            data(tokens),
+            [
+                Token::String("bad  escape".to_owned()),
+                Token::String("open".to_owned()),
+                Token::Ident("next".to_owned()),
+                Token::Char(char::REPLACEMENT_CHARACTER),
+                Token::Char('a'),
+            ]
+        );
+
+        match &errors[..] {
+            [ScanError::BadEscape(escape), ScanError::UnterminatedString(string), ScanError::EmptyChar(_), ScanError::OverlongChar(_)] =>
+            {
+                assert_eq!(escape.data, "\\q");
+                assert_eq!((escape.start.col, escape.end.col), (5, 7));
+                assert_eq!((string.start.col, string.end.col), (16, 21));
+            }
+            errors => panic!("{errors:?}"),
+        }
+    }
+
+    #[test]
+    fn substates_comments() {
+        let (tokens, errors) = lex("a // line\n/* outer /* inner */ */ b / c /* open");
+
+        assert_eq!(
+            data(tokens),
+            [
+                Token::Ident("a".to_owned()),
+                Token::Comment(" line".to_owned()),
+                Token::Ident("b".to_owned()),
+                Token::Slash,
+                Token::Ident("c".to_owned()),
+            ]
+        );
+
+        assert!(matches!(errors[..], [ScanError::UnterminatedComment(_)]));
+    }
+}
@@ -0,0 +1,266 @@
+use ruinous_util::span::{Location, Span};
+
+use super::{ScanError, Step, SubState};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NumberLiteral {
+    Integer { value: u128, radix: u32 },
+    Float(f64),
+}
+
+#[derive(Clone)]
+pub struct Number {
+    hex: bool,
+    octal: bool,
+    binary: bool,
+    floats: bool,
+    underscores: bool,
+    active: Option<Active>,
+}
+
+#[derive(Clone)]
+struct Active {
+    start: Location,
+    end: Location,
+    digits: String,
+    radix: u32,
+    phase: Phase,
+    integer_end: Location,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Phase {
+    Zero,
+    Prefixed,
+    Integer,
+    Dot,
+    Fraction,
+    ExponentStart,
+    ExponentSign,
+    Exponent,
+}
+
+impl Default for Number {
+    fn default() -> Self {
+        Self {
+            hex: true,
+            octal: true,
+            binary: true,
+            floats: true,
+            underscores: true,
+            active: None,
+        }
+    }
+}
+
+impl Number {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[must_use]
+    pub fn hex(self, hex: bool) -> Self {
+        Self { hex, ..self }
+    }
+
+    #[must_use]
+    pub fn octal(self, octal: bool) -> Self {
+        Self { octal, ..self }
+    }
+
+    #[must_use]
+    pub fn binary(self, binary: bool) -> Self {
+        Self { binary, ..self }
+    }
+
+    #[must_use]
+    pub fn floats(self, floats: bool) -> Self {
+        Self { floats, ..self }
+    }
+
+    #[must_use]
+    pub fn underscores(self, underscores: bool) -> Self {
+        Self {
+            underscores,
+            ..self
+        }
+    }
+
+    fn prefix(&self, char: char) -> Option<u32> {
+        match char {
+            'x' | 'X' if self.hex => Some(16),
+            'o' | 'O' if self.octal => Some(8),
+            'b' | 'B' if self.binary => Some(2),
+            _ => None,
+        }
+    }
+}
+
+impl Active {
+    fn consume(&mut self, input: Span<char>, phase: Phase) -> Step<NumberLiteral> {
+        if input.data != '_' {
+            self.digits.push(input.data);
+        }
+
+        self.end = input.end;
+        self.phase = phase;
+        Step::Continue
+    }
+
+    fn span(&self) -> Span<()> {
+        Span::new(self.start, self.end, ())
+    }
+
+    fn integer(&self, end: Location, errors: &mut Vec<ScanError>) -> Step<NumberLiteral> {
+        let digits = match self.radix {
+            10 => self.digits.trim_end_matches('.'),
+            _ => &self.digits[2..],
+        };
+
+        let value = if digits.is_empty() {
+            errors.push(ScanError::MissingDigits(self.span()));
+            0
+        } else {
+            u128::from_str_radix(digits, self.radix).unwrap_or_else(|_| {
+                errors.push(ScanError::IntegerOverflow(self.span()));
+                u128::MAX
+            })
+        };
+
+        Step::Done(Span::new(
+            self.start,
+            end,
+            NumberLiteral::Integer {
+                value,
+                radix: self.radix,
+            },
+        ))
+    }
+
+    fn float(&self, errors: &mut Vec<ScanError>) -> Step<NumberLiteral> {
+        let mut digits = self.digits.as_str();
+
+        if matches!(self.phase, Phase::ExponentStart | Phase::ExponentSign) {
+            errors.push(ScanError::MissingExponent(self.span()));
+            digits = digits.trim_end_matches(['e', 'E', '+', '-']);
+        }
+
+        let value = digits.parse().unwrap_or(f64::NAN);
+        Step::Done(Span::new(self.start, self.end, NumberLiteral::Float(value)))
+    }
+}
+
+impl SubState for Number {
+    type Output = NumberLiteral;
+
+    fn accepts(&self, first: char) -> bool {
+        first.is_ascii_digit()
+    }
+
+    fn process(&mut self, input: Span<char>, errors: &mut Vec<ScanError>) -> Step<Self::Output> {
+        let char = input.data;
+        let prefix = self.prefix(char);
+
+        let Some(active) = &mut self.active else {
+            let phase = if char == '0' {
+                Phase::Zero
+            } else {
+                Phase::Integer
+            };
+
+            self.active = Some(Active {
+                start: input.start,
+                end: input.end,
+                digits: char.to_string(),
+                radix: 10,
+                phase,
+                integer_end: input.end,
+            });
+
+            return Step::Continue;
+        };
+
+        let underscore = char == '_' && self.underscores;
+
+        match active.phase {
+            Phase::Zero => {
+                if let Some(radix) = prefix {
+                    active.radix = radix;
+                    return active.consume(input, Phase::Prefixed);
+                }
+            }
+            Phase::Prefixed => {
+                if char.is_digit(active.radix) || underscore {
+                    return active.consume(input, Phase::Prefixed);
+                }
+
+                if char.is_ascii_digit() {
+                    errors.push(ScanError::InvalidDigit(input));
+                    active.end = input.end;
+                    return Step::Continue;
+                }
+
+                let end = active.end;
+                let step = active.integer(end, errors);
+                self.active = None;
+                return step;
+            }
+            Phase::Dot => {
+                if char.is_ascii_digit() {
+                    return active.consume(input, Phase::Fraction);
+                }
+
+                let end = active.integer_end;
+                let step = active.integer(end, errors);
+                self.active = None;
+                return step;
+            }
+            Phase::Fraction | Phase::Exponent if char.is_ascii_digit() || underscore => {
+                let phase = active.phase;
+                return active.consume(input, phase);
+            }
+            Phase::ExponentStart if char == '+' || char == '-' => {
+                return active.consume(input, Phase::ExponentSign);
+            }
+            Phase::ExponentStart | Phase::ExponentSign if char.is_ascii_digit() => {
+                return active.consume(input, Phase::Exponent);
+            }
+            _ => (),
+        }
+
+        if matches!(active.phase, Phase::Zero | Phase::Integer) {
+            if char.is_ascii_digit() || underscore {
+                active.integer_end = input.end;
+                return active.consume(input, Phase::Integer);
+            }
+
+            if self.floats && char == '.' {
+                active.integer_end = active.end;
+                return active.consume(input, Phase::Dot);
+            }
+        }
+
+        if self.floats
+            && (char == 'e' || char == 'E')
+            && matches!(active.phase, Phase::Zero | Phase::Integer | Phase::Fraction)
+        {
+            return active.consume(input, Phase::ExponentStart);
+        }
+
+        self.finish(errors)
+    }
+
+    fn finish(&mut self, errors: &mut Vec<ScanError>) -> Step<Self::Output> {
+        let Some(active) = self.active.take() else {
+            return Step::Reject;
+        };
+
+        match active.phase {
+            Phase::Zero | Phase::Integer | Phase::Prefixed => active.integer(active.end, errors),
+            Phase::Dot => active.integer(active.integer_end, errors),
+            Phase::Fraction | Phase::ExponentStart | Phase::ExponentSign | Phase::Exponent => {
+                active.float(errors)
+            }
+        }
+    }
+}
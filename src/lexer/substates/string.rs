@@ -0,0 +1,222 @@
+use ruinous_util::span::{Location, Span};
+
+use super::{
+    escape::{Escape, EscapeStep},
+    ScanError, Step, SubState,
+};
+
+#[derive(Clone)]
+pub struct StringLiteral {
+    quote: char,
+    multiline: bool,
+    escapes: bool,
+    active: Option<Quoted>,
+}
+
+#[derive(Clone)]
+pub struct CharLiteral {
+    quote: char,
+    active: Option<Quoted>,
+}
+
+#[derive(Clone)]
+struct Quoted {
+    start: Location,
+    end: Location,
+    value: String,
+    escape: Escape,
+}
+
+enum QuotedStep {
+    Continue,
+    Closed,
+    Unterminated,
+}
+
+impl Default for StringLiteral {
+    fn default() -> Self {
+        Self {
+            quote: '"',
+            multiline: false,
+            escapes: true,
+            active: None,
+        }
+    }
+}
+
+impl Default for CharLiteral {
+    fn default() -> Self {
+        Self {
+            quote: '\'',
+            active: None,
+        }
+    }
+}
+
+impl StringLiteral {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[must_use]
+    pub fn quote(self, quote: char) -> Self {
+        Self { quote, ..self }
+    }
+
+    #[must_use]
+    pub fn multiline(self, multiline: bool) -> Self {
+        Self { multiline, ..self }
+    }
+
+    #[must_use]
+    pub fn escapes(self, escapes: bool) -> Self {
+        Self { escapes, ..self }
+    }
+}
+
+impl CharLiteral {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[must_use]
+    pub fn quote(self, quote: char) -> Self {
+        Self { quote, ..self }
+    }
+}
+
+impl Quoted {
+    fn new(input: Span<char>) -> Self {
+        Self {
+            start: input.start,
+            end: input.end,
+            value: String::new(),
+            escape: Escape::default(),
+        }
+    }
+
+    fn process(
+        &mut self,
+        input: Span<char>,
+        quote: char,
+        multiline: bool,
+        escapes: bool,
+        errors: &mut Vec<ScanError>,
+    ) -> QuotedStep {
+        let step = if escapes {
+            self.escape.process(input, errors)
+        } else {
+            EscapeStep::Literal
+        };
+
+        match step {
+            EscapeStep::Literal if input.data == quote => {
+                self.end = input.end;
+                return QuotedStep::Closed;
+            }
+            EscapeStep::Literal if input.data == '\n' && !multiline => {
+                return QuotedStep::Unterminated;
+            }
+            EscapeStep::Literal => self.value.push(input.data),
+            EscapeStep::Char(char) => self.value.push(char),
+            EscapeStep::Pending | EscapeStep::Invalid { consumed: true } => (),
+            EscapeStep::Invalid { consumed: false } => {
+                return self.process(input, quote, multiline, escapes, errors);
+            }
+        }
+
+        self.end = input.end;
+        QuotedStep::Continue
+    }
+
+    fn span(&self) -> Span<()> {
+        Span::new(self.start, self.end, ())
+    }
+}
+
+impl SubState for StringLiteral {
+    type Output = String;
+
+    fn accepts(&self, first: char) -> bool {
+        first == self.quote
+    }
+
+    fn process(&mut self, input: Span<char>, errors: &mut Vec<ScanError>) -> Step<Self::Output> {
+        let Some(active) = &mut self.active else {
+            self.active = Some(Quoted::new(input));
+            return Step::Continue;
+        };
+
+        match active.process(input, self.quote, self.multiline, self.escapes, errors) {
+            QuotedStep::Continue => Step::Continue,
+            QuotedStep::Closed => {
+                let active = self.active.take().unwrap_or_else(|| unreachable!());
+                Step::Done(Span::new(active.start, active.end, active.value))
+            }
+            QuotedStep::Unterminated => self.finish(errors),
+        }
+    }
+
+    fn finish(&mut self, errors: &mut Vec<ScanError>) -> Step<Self::Output> {
+        match self.active.take() {
+            Some(active) => {
+                errors.push(ScanError::UnterminatedString(active.span()));
+                Step::Done(Span::new(active.start, active.end, active.value))
+            }
+            None => Step::Reject,
+        }
+    }
+}
+
+impl SubState for CharLiteral {
+    type Output = char;
+
+    fn accepts(&self, first: char) -> bool {
+        first == self.quote
+    }
+
+    fn process(&mut self, input: Span<char>, errors: &mut Vec<ScanError>) -> Step<Self::Output> {
+        let Some(active) = &mut self.active else {
+            self.active = Some(Quoted::new(input));
+            return Step::Continue;
+        };
+
+        match active.process(input, self.quote, false, true, errors) {
+            QuotedStep::Continue => Step::Continue,
+            QuotedStep::Closed => {
+                let active = self.active.take().unwrap_or_else(|| unreachable!());
+                let mut chars = active.value.chars();
+
+                let value = match (chars.next(), chars.next()) {
+                    (Some(char), None) => char,
+                    (None, _) => {
+                        errors.push(ScanError::EmptyChar(active.span()));
+                        char::REPLACEMENT_CHARACTER
+                    }
+                    (Some(char), Some(_)) => {
+                        errors.push(ScanError::OverlongChar(active.span()));
+                        char
+                    }
+                };
+
+                Step::Done(Span::new(active.start, active.end, value))
+            }
+            QuotedStep::Unterminated => self.finish(errors),
+        }
+    }
+
+    fn finish(&mut self, errors: &mut Vec<ScanError>) -> Step<Self::Output> {
+        match self.active.take() {
+            Some(active) => {
+                errors.push(ScanError::UnterminatedChar(active.span()));
+                let value = active
+                    .value
+                    .chars()
+                    .next()
+                    .unwrap_or(char::REPLACEMENT_CHARACTER);
+                Step::Done(Span::new(active.start, active.end, value))
+            }
+            None => Step::Reject,
+        }
+    }
+}
@@ -0,0 +1,70 @@
+use ruinous_util::span::{Location, Span};
+
+use super::{ScanError, Step, SubState};
+
+#[derive(Clone)]
+pub struct Symbols<Output> {
+    symbols: Vec<(String, Output)>,
+    text: String,
+    start: Option<Location>,
+    matched: Option<(usize, Location)>,
+}
+
+impl<Output: Clone> Symbols<Output> {
+    pub fn new<S: Into<String>, I: IntoIterator<Item = (S, Output)>>(symbols: I) -> Self {
+        Self {
+            symbols: symbols
+                .into_iter()
+                .map(|(symbol, output)| (symbol.into(), output))
+                .filter(|(symbol, _)| !symbol.is_empty())
+                .collect(),
+            text: String::new(),
+            start: None,
+            matched: None,
+        }
+    }
+}
+
+impl<Output: Clone> SubState for Symbols<Output> {
+    type Output = Output;
+
+    fn accepts(&self, first: char) -> bool {
+        self.symbols
+            .iter()
+            .any(|(symbol, _)| symbol.starts_with(first))
+    }
+
+    fn process(&mut self, input: Span<char>, errors: &mut Vec<ScanError>) -> Step<Self::Output> {
+        self.start.get_or_insert(input.start);
+        self.text.push(input.data);
+
+        if !self
+            .symbols
+            .iter()
+            .any(|(symbol, _)| symbol.starts_with(&self.text))
+        {
+            return self.finish(errors);
+        }
+
+        if let Some(index) = self
+            .symbols
+            .iter()
+            .position(|(symbol, _)| *symbol == self.text)
+        {
+            self.matched = Some((index, input.end));
+        }
+
+        Step::Continue
+    }
+
+    fn finish(&mut self, _: &mut Vec<ScanError>) -> Step<Self::Output> {
+        self.text.clear();
+
+        match (self.start.take(), self.matched.take()) {
+            (Some(start), Some((index, end))) => {
+                Step::Done(Span::new(start, end, self.symbols[index].1.clone()))
+            }
+            _ => Step::Reject,
+        }
+    }
+}
@@ -0,0 +1,169 @@
+use std::{collections::VecDeque, io::BufRead};
+
+use ruinous_util::span::Span;
+
+use crate::reader::{CharReader, Continuation};
+
+use super::{state::State, Error};
+
+pub struct Tokens<R, S: State> {
+    reader: CharReader<R>,
+    state: Option<S>,
+    pending: VecDeque<Result<Span<S::Token>, S::Error>>,
+}
+
+impl<R: BufRead, S: State> Tokens<R, S> {
+    pub(crate) fn new(reader: CharReader<R>, state: S) -> Self {
+        Self {
+            reader,
+            state: Some(state),
+            pending: VecDeque::new(),
+        }
+    }
+}
+
+impl<R: BufRead, S: State> Iterator for Tokens<R, S> {
+    type Item = Result<Span<S::Token>, Error<S>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(token) = self.pending.pop_front() {
+                return Some(token.map_err(|error| Error::LexErrors(vec![error].into())));
+            }
+
+            let state = self.state.as_mut()?;
+
+            match self.reader.next_char() {
+                Ok(Some(input)) => {
+                    while let Continuation::Peek = state.process(input, &mut self.pending) {}
+                }
+                Ok(None) => self.state.take()?.finish(&mut self.pending),
+                Err(error) => {
+                    self.state = None;
+                    return Some(Err(error.into()));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ruinous_util::{error::writer::ErrorWriter, span::Location};
+
+    use super::*;
+    use crate::{
+        error::context::ErrorProvider,
+        lexer::{state::Sink, Lexer},
+    };
+
+    #[derive(Debug, PartialEq)]
+    struct Digit(Span<char>);
+
+    impl ErrorProvider for Digit {
+        fn write_errors(&self, writer: &mut dyn ErrorWriter) -> std::fmt::Result {
+            writer.error(Some(self.0.swap(())), "Unexpected digit")
+        }
+    }
+
+    #[derive(Default)]
+    struct Words {
+        current: Option<Span<String>>,
+    }
+
+    impl State for Words {
+        type Token = String;
+        type Error = Digit;
+
+        fn process<S: Sink<Self::Token, Self::Error> + ?Sized>(
+            &mut self,
+            input: Span<char>,
+            sink: &mut S,
+        ) -> Continuation {
+            if input.data.is_ascii_digit() {
+                sink.error(Digit(input));
+                return Continuation::Consume;
+            }
+
+            match (&mut self.current, input.data.is_whitespace()) {
+                (Some(word), false) => {
+                    word.data.push(input.data);
+                    word.end = input.end;
+                }
+                (None, false) => self.current = Some(input.swap(input.data.to_string())),
+                (current, true) => {
+                    if let Some(word) = current.take() {
+                        sink.token(word);
+                    }
+                }
+            }
+
+            Continuation::Consume
+        }
+
+        fn finish<S: Sink<Self::Token, Self::Error> + ?Sized>(self, sink: &mut S) {
+            if let Some(word) = self.current {
+                sink.token(word);
+            }
+        }
+    }
+
+    #[test]
+    fn tokens_pull_on_demand() {
+        let mut tokens = Lexer::with_str("one two\nthree")
+            .tokens(Words::default())
+            .peekable();
+
+        assert_eq!(tokens.peek().unwrap().as_ref().unwrap().data, "one");
+
+        let token = tokens.next().unwrap().unwrap();
+        assert_eq!(token.data, "one");
+        assert_eq!(token.start, Location::new(0, 0, 0));
+        assert_eq!(token.end, Location::new(0, 3, 3));
+
+        let rest = tokens.map(|token| token.unwrap().data).collect::<Vec<_>>();
+
+        assert_eq!(rest, ["two", "three"]);
+    }
+
+    #[test]
+    fn tokens_report_errors_in_order() {
+        let result = Lexer::with_str("a1 b")
+            .tokens(Words::default())
+            .map(|token| match token {
+                Ok(token) => Ok(token.data),
+                Err(Error::LexErrors(errors)) => Err(errors.errors),
+                Err(error) => panic!("{error:?}"),
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            result,
+            [
+                Err(vec![Digit(Span::new(
+                    Location::new(0, 1, 1),
+                    Location::new(0, 2, 2),
+                    '1'
+                ))]),
+                Ok("a".to_owned()),
+                Ok("b".to_owned()),
+            ]
+        );
+    }
+
+    #[test]
+    fn lex_continues_after_errors() {
+        let mut words = Vec::new();
+
+        let result = Lexer::with_str("1 a 2 b 3").lex(Words::default(), |token| {
+            words.push(token.data);
+        });
+
+        assert_eq!(words, ["a", "b"]);
+
+        match result {
+            Err(Error::LexErrors(errors)) => assert_eq!(errors.errors.len(), 3),
+            other => panic!("{other:?}"),
+        }
+    }
+}
@@ -0,0 +1,222 @@
+use ruinous_util::span::{Location, Span};
+
+use super::state::{Continuation, Sink, State};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Element<Token> {
+    Token { token: Token, text: String },
+    Trivia(String),
+}
+
+#[derive(Clone)]
+pub struct Lossless<S> {
+    inner: S,
+    text: String,
+    consumed: Option<Location>,
+    recorded: usize,
+    end: Location,
+}
+
+struct TriviaSink<'a, K: ?Sized> {
+    sink: &'a mut K,
+    text: &'a mut String,
+    consumed: &'a mut Option<Location>,
+}
+
+impl<Token> Element<Token> {
+    pub fn text(&self) -> &str {
+        match self {
+            Element::Token { text, .. } | Element::Trivia(text) => text,
+        }
+    }
+
+    pub fn token(&self) -> Option<&Token> {
+        match self {
+            Element::Token { token, .. } => Some(token),
+            Element::Trivia(_) => None,
+        }
+    }
+}
+
+impl<S: State> Lossless<S> {
+    pub fn new(inner: S) -> Self {
+        Self {
+            inner,
+            text: String::new(),
+            consumed: None,
+            recorded: 0,
+            end: Location::default(),
+        }
+    }
+}
+
+impl<'a, K: ?Sized> TriviaSink<'a, K> {
+    fn take(&mut self, until: Location) -> Option<Span<String>> {
+        let start = (*self.consumed)?;
+        let length = until.offset.checked_sub(start.offset)?.min(self.text.len());
+
+        *self.consumed = Some(until);
+        Some(Span::new(start, until, self.text.drain(..length).collect()))
+    }
+
+    fn trivia<Token, Error>(&mut self, until: Location)
+    where
+        K: Sink<Element<Token>, Error>,
+    {
+        if let Some(trivia) = self.take(until).filter(|trivia| !trivia.data.is_empty()) {
+            self.sink.token(trivia.map(Element::Trivia));
+        }
+    }
+}
+
+impl<S: State> State for Lossless<S> {
+    type Token = Element<S::Token>;
+    type Error = S::Error;
+
+    fn process<K: Sink<Self::Token, Self::Error> + ?Sized>(
+        &mut self,
+        input: Span<char>,
+        sink: &mut K,
+    ) -> Continuation {
+        self.consumed.get_or_insert(input.start);
+
+        if input.end.offset > self.recorded {
+            self.text.push(input.data);
+            self.recorded = input.end.offset;
+            self.end = input.end;
+        }
+
+        let mut sink = TriviaSink {
+            sink,
+            text: &mut self.text,
+            consumed: &mut self.consumed,
+        };
+
+        self.inner.process(input, &mut sink)
+    }
+
+    fn finish<K: Sink<Self::Token, Self::Error> + ?Sized>(mut self, sink: &mut K) {
+        let mut sink = TriviaSink {
+            sink,
+            text: &mut self.text,
+            consumed: &mut self.consumed,
+        };
+
+        self.inner.finish(&mut sink);
+        sink.trivia(self.end);
+    }
+
+    fn take_pending(&mut self) -> Vec<Span<char>> {
+        let pending = self.inner.take_pending();
+
+        if let (Some(first), Some(consumed)) = (pending.first(), self.consumed) {
+            self.text
+                .truncate(first.start.offset.saturating_sub(consumed.offset));
+            self.recorded = first.start.offset;
+            self.end = first.start;
+        }
+
+        pending
+    }
+}
+
+impl<'a, Token, Error, K> Sink<Token, Error> for TriviaSink<'a, K>
+where
+    K: Sink<Element<Token>, Error> + ?Sized,
+{
+    fn token(&mut self, token: Span<Token>) {
+        self.trivia(token.start);
+
+        let text = self
+            .take(token.end)
+            .map(|text| text.data)
+            .unwrap_or_default();
+        self.sink
+            .token(token.map(|token| Element::Token { token, text }));
+    }
+
+    fn error(&mut self, error: Error) {
+        self.sink.error(error);
+    }
+
+    fn interrupted(&self) -> bool {
+        self.sink.interrupted()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::lexer::{
+        rules::{CharSet, Rules},
+        Lexer,
+    };
+
+    fn lex(input: &str) -> Vec<Span<Element<String>>> {
+        let rules = Rules::new()
+            .class(CharSet::single(';'), str::to_owned)
+            .regex("[a-z]+", str::to_owned)
+            .and_then(|rules| rules.skip(r"\s+"))
+            .and_then(|rules| rules.skip("#[^\r\n]*"))
+            .unwrap()
+            .build();
+
+        let mut elements = Vec::new();
+        let _ = Lexer::with_str(input)
+            .lossless()
+            .lex(Lossless::new(rules), |element| elements.push(element));
+
+        elements
+    }
+
+    #[test]
+    fn trivia_round_trips_input() {
+        let input = "  let x; # comment\r\n\r\ny $ z\r\n";
+        let elements = lex(input);
+
+        assert_eq!(
+            elements
+                .iter()
+                .map(|element| element.data.text())
+                .collect::<String>(),
+            input
+        );
+
+        assert!(elements
+            .iter()
+            .all(|element| element.range().len() == element.data.text().len()));
+
+        assert_eq!(
+            elements
+                .iter()
+                .filter_map(|element| element.data.token().map(String::as_str))
+                .collect::<Vec<_>>(),
+            ["let", "x", ";", "y", "z"]
+        );
+
+        assert_eq!(elements[0].data, Element::Trivia("  ".to_owned()));
+        assert_eq!(
+            elements.last().map(|element| &element.data),
+            Some(&Element::Trivia("\r\n".to_owned()))
+        );
+    }
+
+    #[test]
+    fn trivia_without_final_newline() {
+        let elements = lex("a #b");
+
+        assert_eq!(
+            elements
+                .into_iter()
+                .map(|element| element.data)
+                .collect::<Vec<_>>(),
+            [
+                Element::Token {
+                    token: "a".to_owned(),
+                    text: "a".to_owned()
+                },
+                Element::Trivia(" #b".to_owned()),
+            ]
+        );
+    }
+}
@@ -0,0 +1,86 @@
+use std::path::PathBuf;
+
+use futures_util::io::{AsyncBufRead, AsyncRead, BufReader};
+
+use crate::lexer::{state::State as LexerState, AsyncLexer};
+
+use super::{state::State as ParserState, Error};
+
+pub struct AsyncParser<R> {
+    lexer: AsyncLexer<R>,
+}
+
+impl<R: AsyncRead + Unpin> AsyncParser<BufReader<R>> {
+    pub fn with_reader<N: Into<PathBuf>>(name: N, reader: R) -> Self {
+        let lexer = AsyncLexer::with_reader(name, reader);
+        Self { lexer }
+    }
+}
+
+impl<R: AsyncBufRead + Unpin> AsyncParser<R> {
+    pub fn new(lexer: AsyncLexer<R>) -> Self {
+        Self { lexer }
+    }
+
+    pub async fn parse<LS: LexerState, PS: ParserState<LS::Token>>(
+        self,
+        lexer: LS,
+        mut parser: PS,
+    ) -> Result<PS::Ast, Error<LS, PS>> {
+        let lexed = self.lexer.lex(lexer, |token| parser.process(token)).await;
+        Error::finish(lexed, parser)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use futures_executor::block_on;
+
+    use super::*;
+    use crate::{
+        lexer::rules::{RuleLexer, Rules},
+        parser::{
+            pratt::{Associativity, Pratt, PrattState},
+            Parser,
+        },
+    };
+
+    fn lexer() -> RuleLexer<String> {
+        Rules::new()
+            .regex("[0-9]+|[+*]", str::to_owned)
+            .and_then(|rules| rules.skip(r"\s+"))
+            .unwrap()
+            .build()
+    }
+
+    fn parser() -> PrattState<String, u64> {
+        Pratt::new(|token: &String| token.parse().ok())
+            .infix("+".to_owned(), 1, Associativity::Left, |lhs, _, rhs| {
+                lhs.data + rhs.data
+            })
+            .infix("*".to_owned(), 2, Associativity::Left, |lhs, _, rhs| {
+                lhs.data * rhs.data
+            })
+            .state()
+    }
+
+    #[test]
+    fn async_parse_matches_sync() {
+        let input = "1 + 2 * 3\n+ 4\n";
+
+        let expected = Parser::with_str(input).parse(lexer(), parser()).unwrap();
+        let result = block_on(
+            AsyncParser::with_reader("<upload>", input.as_bytes()).parse(lexer(), parser()),
+        )
+        .unwrap();
+
+        assert_eq!(result, expected);
+        assert_eq!(result.data, 11);
+
+        let result = block_on(
+            AsyncParser::with_reader("<upload>", "1 +".as_bytes()).parse(lexer(), parser()),
+        );
+
+        assert!(matches!(result, Err(Error::ParseErrors(_))));
+    }
+}
@@ -0,0 +1,882 @@
+use std::{
+    cmp::Ordering,
+    fmt::Debug,
+    rc::Rc,
+    sync::{Arc, OnceLock, Weak},
+};
+
+use ruinous_util::span::{Location, Span};
+
+use super::{
+    error::{ParseErrors, SyntaxError},
+    recovery::{Recovery, Resume},
+    state::State,
+};
+
+/// The tokens being parsed by a [`Combinator`].
+pub struct Input<'a, Token> {
+    tokens: &'a [Span<Token>],
+    position: usize,
+    end: Location,
+    furthest: usize,
+    expected: Vec<String>,
+    errors: Vec<SyntaxError>,
+}
+
+pub trait Combinator<Token> {
+    type Output;
+
+    /// A failed combinator may have moved the input, so callers rewind it themselves.
+    fn parse(&self, input: &mut Input<'_, Token>) -> Option<Self::Output>;
+
+    fn map<O, F: Fn(Self::Output) -> O>(self, map: F) -> Map<Self, F>
+    where
+        Self: Sized,
+    {
+        Map { inner: self, map }
+    }
+
+    fn spanned(self) -> Spanned<Self>
+    where
+        Self: Sized,
+    {
+        Spanned { inner: self }
+    }
+
+    fn or<C: Combinator<Token, Output = Self::Output>>(self, other: C) -> Choice<(Self, C)>
+    where
+        Self: Sized,
+    {
+        choice((self, other))
+    }
+
+    fn then<C: Combinator<Token>>(self, other: C) -> Sequence<(Self, C)>
+    where
+        Self: Sized,
+    {
+        sequence((self, other))
+    }
+
+    /// A parser state that buffers every token and runs this combinator over them in
+    /// `finish`, so nothing is parsed or reported until the input ends.
+    fn state(self) -> CombinatorState<Self, Token>
+    where
+        Self: Sized,
+    {
+        CombinatorState {
+            combinator: self,
+            tokens: Vec::new(),
+        }
+    }
+}
+
+/// See [`Combinator::state`].
+pub struct CombinatorState<C, Token> {
+    combinator: C,
+    tokens: Vec<Span<Token>>,
+}
+
+pub struct Just<Token> {
+    token: Token,
+}
+
+pub struct Select<F> {
+    description: String,
+    select: F,
+}
+
+pub struct End;
+
+pub struct Sequence<T> {
+    combinators: T,
+}
+
+pub struct Choice<T> {
+    combinators: T,
+}
+
+pub struct Many<C> {
+    inner: C,
+}
+
+pub struct Optional<C> {
+    inner: C,
+}
+
+pub struct SeparatedBy<C, S> {
+    item: C,
+    separator: S,
+}
+
+pub struct Delimited<O, C, E> {
+    open: O,
+    inner: C,
+    close: E,
+}
+
+pub struct Map<C, F> {
+    inner: C,
+    map: F,
+}
+
+pub struct Spanned<C> {
+    inner: C,
+}
+
+pub struct Recover<C, Token, F> {
+    inner: C,
+    recovery: Recovery<Token>,
+    node: F,
+}
+
+type Definition<Token, O> = OnceLock<Box<dyn Combinator<Token, Output = O> + Send + Sync>>;
+
+/// A combinator that can refer to itself, created with [`recursive`].
+pub struct Recursive<Token, O> {
+    link: Link<Token, O>,
+}
+
+enum Link<Token, O> {
+    Owned(Arc<Definition<Token, O>>),
+    Inner(Weak<Definition<Token, O>>),
+}
+
+pub fn just<Token: PartialEq + Clone + Debug>(token: Token) -> Just<Token> {
+    Just { token }
+}
+
+/// Matches a single token that `select` maps to something, described as `description`
+/// in errors.
+pub fn token<Token, O, F: Fn(&Token) -> Option<O>>(
+    description: impl Into<String>,
+    select: F,
+) -> Select<F> {
+    Select {
+        description: description.into(),
+        select,
+    }
+}
+
+pub fn end() -> End {
+    End
+}
+
+/// Parses `inner`, or records the error and builds a node with `node` from the span
+/// skipped to the next sync point. Errors can't be backtracked out of, so this
+/// belongs around repeated items like statements rather than inside a choice.
+pub fn recover<Token, C: Combinator<Token>, F: Fn(Span<()>) -> C::Output>(
+    inner: C,
+    recovery: Recovery<Token>,
+    node: F,
+) -> Recover<C, Token, F> {
+    Recover {
+        inner,
+        recovery,
+        node,
+    }
+}
+
+pub fn sequence<T>(combinators: T) -> Sequence<T> {
+    Sequence { combinators }
+}
+
+/// Matches the first combinator in a tuple that succeeds.
+pub fn choice<T>(combinators: T) -> Choice<T> {
+    Choice { combinators }
+}
+
+pub fn many<C>(inner: C) -> Many<C> {
+    Many { inner }
+}
+
+pub fn optional<C>(inner: C) -> Optional<C> {
+    Optional { inner }
+}
+
+/// Matches zero or more `item`s with a `separator` between each of them.
+pub fn separated_by<C, S>(item: C, separator: S) -> SeparatedBy<C, S> {
+    SeparatedBy { item, separator }
+}
+
+pub fn delimited<O, C, E>(open: O, inner: C, close: E) -> Delimited<O, C, E> {
+    Delimited { open, inner, close }
+}
+
+/// Defines a combinator in terms of itself, for nested constructs like expressions.
+/// `define` is given a handle to the combinator being defined, which must only be
+/// used inside the combinator it returns.
+pub fn recursive<Token, O, C, F>(define: F) -> Recursive<Token, O>
+where
+    C: Combinator<Token, Output = O> + Send + Sync + 'static,
+    F: FnOnce(Recursive<Token, O>) -> C,
+{
+    let definition = Arc::new(OnceLock::new());
+
+    let inner = define(Recursive {
+        link: Link::Inner(Arc::downgrade(&definition)),
+    });
+
+    if definition.set(Box::new(inner) as Box<_>).is_err() {
+        unreachable!("a recursive combinator is only defined once");
+    }
+
+    Recursive {
+        link: Link::Owned(definition),
+    }
+}
+
+impl<'a, Token> Input<'a, Token> {
+    pub fn new(tokens: &'a [Span<Token>], end: Location) -> Self {
+        Self {
+            tokens,
+            position: 0,
+            end,
+            furthest: 0,
+            expected: Vec::new(),
+            errors: Vec::new(),
+        }
+    }
+
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    pub fn rewind(&mut self, position: usize) {
+        self.position = position;
+    }
+
+    pub fn peek(&self) -> Option<&'a Span<Token>> {
+        self.tokens.get(self.position)
+    }
+
+    pub fn bump(&mut self) -> Option<&'a Span<Token>> {
+        let token = self.peek()?;
+        self.position += 1;
+        Some(token)
+    }
+
+    pub fn expected<D: Into<String>>(&mut self, description: D) {
+        if self.position > self.furthest {
+            self.furthest = self.position;
+            self.expected.clear();
+        }
+
+        let description = description.into();
+
+        if self.position == self.furthest && !self.expected.contains(&description) {
+            self.expected.push(description);
+        }
+    }
+
+    pub fn span(&self, start: usize) -> Span<()> {
+        let location = |position| {
+            self.tokens
+                .get(position)
+                .map_or(self.end, |token: &Span<Token>| token.start)
+        };
+
+        let end = match self.position.checked_sub(1) {
+            Some(last) if last >= start => self.tokens[last].end,
+            _ => location(start),
+        };
+
+        Span::new(location(start), end, ())
+    }
+
+    /// Record the furthest failure since `start`, and skip to the next sync point.
+    pub fn recover(&mut self, start: usize, recovery: &Recovery<Token>) -> Option<Span<()>>
+    where
+        Token: Debug,
+    {
+        self.position = self.furthest.max(start);
+
+        if self.position == start && self.peek().is_none() {
+            return None;
+        }
+
+        self.errors.push(self.error());
+
+        while let Some(token) = self.peek() {
+            match recovery.resume(&token.data) {
+                Some(Resume::Boundary) if self.position > start => break,
+                Some(Resume::Terminator) => {
+                    self.bump();
+                    break;
+                }
+                _ => {
+                    self.bump();
+                }
+            }
+        }
+
+        self.furthest = self.position;
+        self.expected.clear();
+
+        Some(self.span(start))
+    }
+
+    fn restore(&mut self, furthest: usize, expected: Vec<String>) {
+        match furthest.cmp(&self.furthest) {
+            Ordering::Greater => {
+                self.furthest = furthest;
+                self.expected = expected;
+            }
+            Ordering::Equal => {
+                let mut expected = expected;
+
+                for description in self.expected.drain(..) {
+                    if !expected.contains(&description) {
+                        expected.push(description);
+                    }
+                }
+
+                self.expected = expected;
+            }
+            Ordering::Less => {}
+        }
+    }
+
+    /// The errors recovered from so far.
+    pub fn errors(&self) -> &[SyntaxError] {
+        &self.errors
+    }
+
+    /// The error for the furthest point any combinator failed at.
+    pub fn error(&self) -> SyntaxError
+    where
+        Token: Debug,
+    {
+        let found = self.tokens.get(self.furthest);
+
+        SyntaxError {
+            span: found.map_or(Span::new(self.end, self.end, ()), |token| token.swap(())),
+            expected: self.expected.clone(),
+            found: found.map(|token| format!("`{:?}`", token.data)),
+        }
+    }
+}
+
+impl<Token: PartialEq + Clone + Debug> Combinator<Token> for Just<Token> {
+    type Output = Span<Token>;
+
+    fn parse(&self, input: &mut Input<'_, Token>) -> Option<Self::Output> {
+        match input.peek() {
+            Some(token) if token.data == self.token => {
+                input.bump();
+                Some(token.clone())
+            }
+            _ => {
+                input.expected(format!("`{:?}`", self.token));
+                None
+            }
+        }
+    }
+}
+
+impl<Token, O, F: Fn(&Token) -> Option<O>> Combinator<Token> for Select<F> {
+    type Output = Span<O>;
+
+    fn parse(&self, input: &mut Input<'_, Token>) -> Option<Self::Output> {
+        let output = input.peek().and_then(|token| {
+            let output = (self.select)(&token.data)?;
+            Some(Span::new(token.start, token.end, output))
+        });
+
+        match output {
+            Some(output) => {
+                input.bump();
+                Some(output)
+            }
+            None => {
+                input.expected(self.description.clone());
+                None
+            }
+        }
+    }
+}
+
+impl<Token> Combinator<Token> for End {
+    type Output = ();
+
+    fn parse(&self, input: &mut Input<'_, Token>) -> Option<Self::Output> {
+        match input.peek() {
+            Some(_) => {
+                input.expected("end of input");
+                None
+            }
+            None => Some(()),
+        }
+    }
+}
+
+macro_rules! tuples {
+    ($($name:ident $index:tt),*) => {
+        impl<Token, $($name: Combinator<Token>),*> Combinator<Token> for Sequence<($($name,)*)> {
+            type Output = ($($name::Output,)*);
+
+            fn parse(&self, input: &mut Input<'_, Token>) -> Option<Self::Output> {
+                Some(($(self.combinators.$index.parse(input)?,)*))
+            }
+        }
+
+        impl<Token, O, $($name: Combinator<Token, Output = O>),*> Combinator<Token>
+            for Choice<($($name,)*)>
+        {
+            type Output = O;
+
+            fn parse(&self, input: &mut Input<'_, Token>) -> Option<Self::Output> {
+                let start = input.position();
+
+                $(
+                    if let Some(output) = self.combinators.$index.parse(input) {
+                        return Some(output);
+                    }
+
+                    input.rewind(start);
+                )*
+
+                None
+            }
+        }
+    };
+}
+
+tuples!(A 0, B 1);
+tuples!(A 0, B 1, C 2);
+tuples!(A 0, B 1, C 2, D 3);
+tuples!(A 0, B 1, C 2, D 3, E 4);
+tuples!(A 0, B 1, C 2, D 3, E 4, F 5);
+tuples!(A 0, B 1, C 2, D 3, E 4, F 5, G 6);
+tuples!(A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7);
+
+impl<Token, C: Combinator<Token>> Combinator<Token> for Many<C> {
+    type Output = Vec<C::Output>;
+
+    fn parse(&self, input: &mut Input<'_, Token>) -> Option<Self::Output> {
+        let mut outputs = Vec::new();
+
+        loop {
+            let start = input.position();
+
+            match self.inner.parse(input) {
+                Some(output) if input.position() > start => outputs.push(output),
+                _ => {
+                    input.rewind(start);
+                    return Some(outputs);
+                }
+            }
+        }
+    }
+}
+
+impl<Token, C: Combinator<Token>> Combinator<Token> for Optional<C> {
+    type Output = Option<C::Output>;
+
+    fn parse(&self, input: &mut Input<'_, Token>) -> Option<Self::Output> {
+        let start = input.position();
+        let output = self.inner.parse(input);
+
+        if output.is_none() {
+            input.rewind(start);
+        }
+
+        Some(output)
+    }
+}
+
+impl<Token, C: Combinator<Token>, S: Combinator<Token>> Combinator<Token> for SeparatedBy<C, S> {
+    type Output = Vec<C::Output>;
+
+    fn parse(&self, input: &mut Input<'_, Token>) -> Option<Self::Output> {
+        let mut start = input.position();
+        let mut outputs = Vec::new();
+
+        loop {
+            match self.item.parse(input) {
+                Some(output) => outputs.push(output),
+                None => {
+                    input.rewind(start);
+                    return Some(outputs);
+                }
+            }
+
+            start = input.position();
+
+            if self.separator.parse(input).is_none() {
+                input.rewind(start);
+                return Some(outputs);
+            }
+        }
+    }
+}
+
+impl<Token, O, C, E> Combinator<Token> for Delimited<O, C, E>
+where
+    O: Combinator<Token>,
+    C: Combinator<Token>,
+    E: Combinator<Token>,
+{
+    type Output = C::Output;
+
+    fn parse(&self, input: &mut Input<'_, Token>) -> Option<Self::Output> {
+        self.open.parse(input)?;
+        let output = self.inner.parse(input)?;
+        self.close.parse(input)?;
+        Some(output)
+    }
+}
+
+impl<Token, O, C: Combinator<Token>, F: Fn(C::Output) -> O> Combinator<Token> for Map<C, F> {
+    type Output = O;
+
+    fn parse(&self, input: &mut Input<'_, Token>) -> Option<Self::Output> {
+        self.inner.parse(input).map(&self.map)
+    }
+}
+
+impl<Token, C: Combinator<Token>> Combinator<Token> for Spanned<C> {
+    type Output = Span<C::Output>;
+
+    fn parse(&self, input: &mut Input<'_, Token>) -> Option<Self::Output> {
+        let start = input.position();
+        let output = self.inner.parse(input)?;
+        Some(input.span(start).swap(output))
+    }
+}
+
+impl<Token: Debug, C: Combinator<Token>, F: Fn(Span<()>) -> C::Output> Combinator<Token>
+    for Recover<C, Token, F>
+{
+    type Output = C::Output;
+
+    fn parse(&self, input: &mut Input<'_, Token>) -> Option<Self::Output> {
+        let start = input.position();
+        let furthest = std::mem::replace(&mut input.furthest, start);
+        let expected = std::mem::take(&mut input.expected);
+
+        if let Some(output) = self.inner.parse(input) {
+            input.restore(furthest, expected);
+            return Some(output);
+        }
+
+        match input.recover(start, &self.recovery) {
+            Some(span) => Some((self.node)(span)),
+            None => {
+                input.restore(furthest, expected);
+                None
+            }
+        }
+    }
+}
+
+impl<Token, O> Combinator<Token> for Recursive<Token, O> {
+    type Output = O;
+
+    fn parse(&self, input: &mut Input<'_, Token>) -> Option<Self::Output> {
+        let definition = match &self.link {
+            Link::Owned(definition) => definition.clone(),
+            Link::Inner(definition) => definition
+                .upgrade()
+                .expect("recursive combinators are only used inside their definition"),
+        };
+
+        definition
+            .get()
+            .expect("recursive combinators are defined before they're used")
+            .parse(input)
+    }
+}
+
+impl<Token, O> Clone for Recursive<Token, O> {
+    fn clone(&self) -> Self {
+        let link = match &self.link {
+            Link::Owned(definition) => Link::Owned(definition.clone()),
+            Link::Inner(definition) => Link::Inner(definition.clone()),
+        };
+
+        Self { link }
+    }
+}
+
+impl<Token, C: Combinator<Token> + ?Sized> Combinator<Token> for &C {
+    type Output = C::Output;
+
+    fn parse(&self, input: &mut Input<'_, Token>) -> Option<Self::Output> {
+        (**self).parse(input)
+    }
+}
+
+impl<Token, C: Combinator<Token> + ?Sized> Combinator<Token> for Box<C> {
+    type Output = C::Output;
+
+    fn parse(&self, input: &mut Input<'_, Token>) -> Option<Self::Output> {
+        (**self).parse(input)
+    }
+}
+
+impl<Token, C: Combinator<Token> + ?Sized> Combinator<Token> for Rc<C> {
+    type Output = C::Output;
+
+    fn parse(&self, input: &mut Input<'_, Token>) -> Option<Self::Output> {
+        (**self).parse(input)
+    }
+}
+
+impl<Token, C: Combinator<Token> + ?Sized> Combinator<Token> for Arc<C> {
+    type Output = C::Output;
+
+    fn parse(&self, input: &mut Input<'_, Token>) -> Option<Self::Output> {
+        (**self).parse(input)
+    }
+}
+
+impl<Token: Debug, C: Combinator<Token>> State<Token> for CombinatorState<C, Token> {
+    type Ast = C::Output;
+    type Error = SyntaxError;
+
+    fn process(&mut self, token: Span<Token>) {
+        self.tokens.push(token);
+    }
+
+    fn finish(self) -> Result<Self::Ast, ParseErrors<Self::Error>> {
+        let end = self
+            .tokens
+            .last()
+            .map_or(Location::default(), |token| token.end);
+
+        let mut input = Input::new(&self.tokens, end);
+
+        let ast = sequence((self.combinator, End)).parse(&mut input);
+
+        match ast {
+            Some((ast, ())) if input.errors.is_empty() => Ok(ast),
+            Some(_) => Err(input.errors.into()),
+            None => {
+                let error = input.error();
+                input.errors.push(error);
+                Err(input.errors.into())
+            }
+        }
+    }
+}
+
+impl<C: Clone, Token> Clone for CombinatorState<C, Token>
+where
+    Span<Token>: Clone,
+{
+    fn clone(&self) -> Self {
+        Self {
+            combinator: self.combinator.clone(),
+            tokens: self.tokens.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        lexer::{
+            rules::{RuleLexer, Rules},
+            Lexer,
+        },
+        parser::{Error, Parser},
+    };
+
+    #[derive(Debug, Clone, PartialEq)]
+    enum Token {
+        Let,
+        Ident(String),
+        Number(u64),
+        Equals,
+        Comma,
+        Semi,
+        Open,
+        Close,
+    }
+
+    #[derive(Debug, PartialEq)]
+    enum Value {
+        Number(u64),
+        Ident(String),
+        List(Vec<Span<Value>>),
+    }
+
+    fn lexer() -> RuleLexer<Token> {
+        Rules::new()
+            .literal("let", |_| Token::Let)
+            .literal("=", |_| Token::Equals)
+            .literal(",", |_| Token::Comma)
+            .literal(";", |_| Token::Semi)
+            .literal("[", |_| Token::Open)
+            .literal("]", |_| Token::Close)
+            .regex("[a-z]+", |text| Token::Ident(text.to_owned()))
+            .and_then(|rules| rules.regex(r"\d+", |text| Token::Number(text.parse().unwrap())))
+            .and_then(|rules| rules.skip(r"\s+"))
+            .unwrap()
+            .build()
+    }
+
+    fn scalar() -> impl Combinator<Token, Output = Span<Value>> {
+        token("number", |token: &Token| match token {
+            Token::Number(number) => Some(Value::Number(*number)),
+            _ => None,
+        })
+        .or(ident().map(|span| span.map(Value::Ident)))
+    }
+
+    fn ident() -> impl Combinator<Token, Output = Span<String>> {
+        token("identifier", |token: &Token| match token {
+            Token::Ident(ident) => Some(ident.clone()),
+            _ => None,
+        })
+    }
+
+    fn value() -> impl Combinator<Token, Output = Span<Value>> {
+        recursive(|value| {
+            let list = delimited(
+                just(Token::Open),
+                separated_by(value, just(Token::Comma)),
+                just(Token::Close),
+            )
+            .map(Value::List)
+            .spanned();
+
+            choice((list, scalar()))
+        })
+    }
+
+    fn statement() -> impl Combinator<Token, Output = (String, Span<Value>)> {
+        sequence((
+            just(Token::Let),
+            ident(),
+            just(Token::Equals),
+            value(),
+            optional(just(Token::Semi)),
+        ))
+        .map(|(_, name, _, value, _)| (name.data, value))
+    }
+
+    fn parse(input: &str) -> Result<Vec<(String, Span<Value>)>, Vec<SyntaxError>> {
+        match Parser::with_str(input).parse(lexer(), many(statement()).state()) {
+            Ok(ast) => Ok(ast),
+            Err(Error::ParseErrors(errors)) => Err(errors.errors),
+            Err(error) => panic!("{error:?}"),
+        }
+    }
+
+    #[test]
+    fn combinators_build_spanned_ast() {
+        let ast = parse("let a = 1;\nlet b = [a, [2, []]]").unwrap();
+
+        assert_eq!(ast[0].0, "a");
+        assert_eq!(ast[0].1.data, Value::Number(1));
+        assert_eq!(ast[1].0, "b");
+        assert_eq!(ast[1].1.range(), 19..31);
+
+        let Value::List(items) = &ast[1].1.data else {
+            panic!("{:?}", ast[1].1);
+        };
+
+        assert_eq!(items[0].data, Value::Ident("a".to_owned()));
+        assert_eq!(items[1].range(), 23..30);
+
+        let Value::List(nested) = &items[1].data else {
+            panic!("{:?}", items[1]);
+        };
+
+        assert_eq!(nested[0].data, Value::Number(2));
+        assert_eq!(nested[1].data, Value::List(Vec::new()));
+    }
+
+    #[test]
+    fn combinators_report_furthest_failure() {
+        let errors = parse("let a = [1, 2;").unwrap_err();
+
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].span.range(), 13..14);
+        assert_eq!(errors[0].expected, ["`Comma`", "`Close`"]);
+        assert_eq!(errors[0].found.as_deref(), Some("`Semi`"));
+
+        let errors = parse("let = 1").unwrap_err();
+        assert_eq!(errors[0].expected, ["identifier"]);
+
+        let errors = parse("let a =").unwrap_err();
+        assert_eq!(errors[0].expected, ["`Open`", "number", "identifier"]);
+        assert_eq!(errors[0].found, None);
+    }
+
+    #[test]
+    fn combinators_recover_at_sync_points() {
+        let tokens = Lexer::with_str("let a = [1, 2;\nlet b = 3;\nlet = 4;\nlet c let d = 5")
+            .tokens(lexer())
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        let recovery = Recovery::new().terminator(Token::Semi).boundary(Token::Let);
+
+        let statements = many(recover(statement().map(Ok), recovery, Err));
+
+        let mut input = Input::new(&tokens, Location::default());
+        let ast = statements.parse(&mut input).unwrap();
+
+        assert_eq!(
+            ast.iter()
+                .map(|statement| match statement {
+                    Ok((name, _)) => Ok(name.as_str()),
+                    Err(span) => Err(span.range()),
+                })
+                .collect::<Vec<_>>(),
+            [Err(0..14), Ok("b"), Err(26..34), Err(35..40), Ok("d")]
+        );
+
+        assert_eq!(
+            input
+                .errors()
+                .iter()
+                .map(|error| (error.span.range(), error.expected.clone()))
+                .collect::<Vec<_>>(),
+            [
+                (13..14, vec!["`Comma`".to_owned(), "`Close`".to_owned()]),
+                (30..31, vec!["identifier".to_owned()]),
+                (41..44, vec!["`Equals`".to_owned()]),
+            ]
+        );
+    }
+
+    #[test]
+    fn combinators_recover_from_failures_since_start() {
+        let tokens = Lexer::with_str("let = 1;\nlet b = 2;")
+            .tokens(lexer())
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        let recovery = Recovery::new().terminator(Token::Semi);
+        let statements = many(recover(statement().map(Ok), recovery, Err));
+
+        let mut input = Input::new(&tokens, Location::default());
+
+        while input.position() < 3 {
+            input.bump();
+        }
+
+        input.expected("something else");
+        input.rewind(0);
+
+        let ast = statements.parse(&mut input).unwrap();
+
+        assert_eq!(
+            ast.iter()
+                .map(|statement| match statement {
+                    Ok((name, _)) => Ok(name.as_str()),
+                    Err(span) => Err(span.range()),
+                })
+                .collect::<Vec<_>>(),
+            [Err(0..8), Ok("b")]
+        );
+
+        assert_eq!(input.errors().len(), 1);
+        assert_eq!(input.errors()[0].span.range(), 4..5);
+        assert_eq!(input.errors()[0].expected, ["identifier"]);
+    }
+}
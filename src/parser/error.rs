@@ -1,28 +1,59 @@
 use std::fmt::Debug;
 
-use ruinous_util::error::{context::ErrorProvider, writer::ErrorWriter};
+use ruinous_util::{
+    error::{context::ErrorProvider, writer::ErrorWriter},
+    span::Span,
+};
 
 use super::state::State as ParserState;
 use crate::{
-    lexer::{state::State as LexerState, Error as LexError},
+    lexer::{state::State as LexerState, Error as LexError, LexErrors},
     reader::error::FileError,
 };
 
 pub enum Error<L: LexerState, P: ParserState<L::Token>> {
     LexError(LexError<L>),
     ParseErrors(ParseErrors<P::Error>),
+    /// Errors from both lexing and parsing, where the parser saw every token that could
+    /// be lexed.
+    LexAndParseErrors(LexErrors<L::Error>, ParseErrors<P::Error>),
+    /// Errors from lexing, with the AST that was parsed from every token that could be
+    /// lexed.
+    LexErrorsWithAst(LexErrors<L::Error>, P::Ast),
 }
 
 pub struct ParseErrors<E: ErrorProvider> {
     pub errors: Vec<E>,
 }
 
+/// A token that didn't match anything the grammar expected at that point, or the end
+/// of the input (when `found` is `None`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyntaxError {
+    pub span: Span<()>,
+    pub expected: Vec<String>,
+    pub found: Option<String>,
+}
+
 impl<E: ErrorProvider> From<Vec<E>> for ParseErrors<E> {
     fn from(errors: Vec<E>) -> Self {
         Self { errors }
     }
 }
 
+impl<L: LexerState, P: ParserState<L::Token>> Error<L, P> {
+    pub(crate) fn finish(lexed: Result<(), LexError<L>>, parser: P) -> Result<P::Ast, Self> {
+        match lexed {
+            Ok(()) => parser.finish().map_err(Error::ParseErrors),
+            Err(LexError::LexErrors(lex)) => match parser.finish() {
+                Ok(ast) => Err(Error::LexErrorsWithAst(lex, ast)),
+                Err(parse) => Err(Error::LexAndParseErrors(lex, parse)),
+            },
+            Err(error) => Err(Error::LexError(error)),
+        }
+    }
+}
+
 impl<L: LexerState, P: ParserState<L::Token>> From<FileError> for Error<L, P> {
     fn from(error: FileError) -> Self {
         Self::LexError(error.into())
@@ -34,6 +65,15 @@ impl<L: LexerState, P: ParserState<L::Token>> Debug for Error<L, P> {
         match self {
             Error::LexError(error) => f.debug_tuple("LexError").field(&error).finish(),
             Error::ParseErrors(error) => f.debug_tuple("ParseErrors").field(&error).finish(),
+            Error::LexAndParseErrors(lex, parse) => f
+                .debug_tuple("LexAndParseErrors")
+                .field(&lex)
+                .field(&parse)
+                .finish(),
+            Error::LexErrorsWithAst(lex, _) => f
+                .debug_tuple("LexErrorsWithAst")
+                .field(&lex)
+                .finish_non_exhaustive(),
         }
     }
 }
@@ -55,8 +95,30 @@ impl<L: LexerState, P: ParserState<L::Token>> ErrorProvider for Error<L, P> {
                     error.write_errors(writer)?
                 }
             }
+            Error::LexErrorsWithAst(lex, _) => lex.write_errors(writer)?,
+            Error::LexAndParseErrors(lex, parse) => {
+                lex.write_errors(writer)?;
+
+                for error in &parse.errors {
+                    error.write_errors(writer)?
+                }
+            }
         }
 
         Ok(())
     }
 }
+
+impl ErrorProvider for SyntaxError {
+    fn write_errors(&self, writer: &mut dyn ErrorWriter) -> std::fmt::Result {
+        let found = self.found.as_deref().unwrap_or("end of input");
+
+        let message = match self.expected.split_last() {
+            None => format!("Unexpected {found}"),
+            Some((last, [])) => format!("Expected {last}, found {found}"),
+            Some((last, rest)) => format!("Expected {} or {last}, found {found}", rest.join(", ")),
+        };
+
+        writer.error(Some(self.span), &message)
+    }
+}
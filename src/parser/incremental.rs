@@ -0,0 +1,127 @@
+use ruinous_util::span::Span;
+
+use super::{error::ParseErrors, state::State as ParserState};
+use crate::{
+    lexer::{
+        incremental::{Changed, IncrementalLexer},
+        state::Checkpoint,
+    },
+    reader::error::FileError,
+};
+
+const CHECKPOINT_INTERVAL: usize = 64;
+
+/// Parses a string in memory with an [`IncrementalLexer`], re-parsing after an edit
+/// from the last copy of the parser state before it. Parser states can't be compared,
+/// so only the tokens before the edit are skipped, and every parse still runs to the
+/// end of the tokens.
+pub struct IncrementalParser<L: Checkpoint, P> {
+    lexer: IncrementalLexer<L>,
+    checkpoints: Vec<(usize, P)>,
+}
+
+impl<L: Checkpoint, P: ParserState<L::Token> + Clone> IncrementalParser<L, P>
+where
+    L::Token: Clone,
+{
+    pub fn new<T: Into<String>>(text: T, lexer: L, parser: P) -> Result<Self, FileError> {
+        Ok(Self {
+            lexer: IncrementalLexer::new(text, lexer)?,
+            checkpoints: vec![(0, parser)],
+        })
+    }
+
+    pub fn lexer(&self) -> &IncrementalLexer<L> {
+        &self.lexer
+    }
+
+    pub fn edit<Data>(&mut self, span: &Span<Data>, text: &str) -> Result<Changed, FileError> {
+        let changed = self.lexer.edit(span, text)?;
+
+        let kept = self
+            .checkpoints
+            .partition_point(|(index, _)| *index <= changed.removed.start);
+
+        self.checkpoints.truncate(kept.max(1));
+        Ok(changed)
+    }
+
+    /// Lexing errors are not included, see [`IncrementalLexer::errors`].
+    pub fn parse(&mut self) -> Result<P::Ast, ParseErrors<P::Error>> {
+        let (start, state) = self
+            .checkpoints
+            .last()
+            .expect("there is always a first checkpoint");
+        let start = *start;
+        let mut state = state.clone();
+
+        for (index, token) in self.lexer.tokens().iter().enumerate().skip(start) {
+            if index > start && index % CHECKPOINT_INTERVAL == 0 {
+                self.checkpoints.push((index, state.clone()));
+            }
+
+            state.process(Span::new(token.start, token.end, token.data.clone()));
+        }
+
+        state.finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{cell::Cell, rc::Rc};
+
+    use ruinous_util::span::Location;
+
+    use super::*;
+    use crate::lexer::rules::{RuleError, Rules};
+
+    #[derive(Clone, Default)]
+    struct Sum {
+        total: u64,
+        replayed: Rc<Cell<usize>>,
+    }
+
+    impl ParserState<u64> for Sum {
+        type Ast = u64;
+        type Error = RuleError;
+
+        fn process(&mut self, token: Span<u64>) {
+            self.total += token.data;
+            self.replayed.set(self.replayed.get() + 1);
+        }
+
+        fn finish(self) -> Result<Self::Ast, ParseErrors<Self::Error>> {
+            Ok(self.total)
+        }
+    }
+
+    #[test]
+    fn incremental_parse_replays_from_checkpoint() {
+        let rules = Rules::new()
+            .regex(r"\d+", |text| text.parse().unwrap())
+            .and_then(|rules| rules.skip(r"\s+"))
+            .unwrap()
+            .build();
+
+        let sum = Sum::default();
+        let replayed = sum.replayed.clone();
+
+        let mut parser = IncrementalParser::new("1\n".repeat(200), rules, sum).unwrap();
+        assert_eq!(parser.parse().ok(), Some(200));
+        assert_eq!(replayed.replace(0), 200);
+
+        let location = |offset| Location {
+            offset,
+            ..Location::default()
+        };
+
+        let changed = parser
+            .edit(&Span::new(location(300), location(301), ()), "5")
+            .unwrap();
+        assert_eq!(changed.inserted, 150..151);
+
+        assert_eq!(parser.parse().ok(), Some(204));
+        assert_eq!(replayed.get(), 72);
+    }
+}
@@ -0,0 +1,254 @@
+use std::fmt::Debug;
+
+use ruinous_util::span::{Location, Span};
+
+use super::error::{ParseErrors, SyntaxError};
+
+/// Parse tables generated by [`grammar!`](super::grammar).
+pub struct Tables<Token, Value> {
+    /// The names of each terminal, with `error` and then the end of input last.
+    pub terminals: &'static [&'static str],
+    /// The actions for each state, sorted by terminal.
+    pub actions: &'static [&'static [(usize, Action)]],
+    /// The state to go to after reducing to each nonterminal in each state, sorted by
+    /// nonterminal.
+    pub gotos: &'static [&'static [(usize, usize)]],
+    /// The nonterminal and number of symbols of each production.
+    pub productions: &'static [(usize, usize)],
+    /// The terminals that recovery can resume at after an error.
+    pub sync: &'static [usize],
+    /// The terminal for a token, or `None` if the grammar never uses it.
+    pub terminal: fn(&Token) -> Option<usize>,
+    /// Runs the action of a production on the values of its symbols and their span.
+    pub reduce: fn(usize, Vec<Span<Value>>, Span<()>) -> Value,
+}
+
+/// What to do on a terminal, with the state to shift to or the production to reduce by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Shift(usize),
+    Reduce(usize),
+    Accept,
+}
+
+/// Runs the tables generated for a grammar, using the default value for `error`.
+pub struct Driver<Token: 'static, Value: 'static> {
+    tables: &'static Tables<Token, Value>,
+    states: Vec<usize>,
+    values: Vec<Span<Value>>,
+    errors: Vec<SyntaxError>,
+    recovering: bool,
+    skipping: bool,
+    end: Location,
+}
+
+impl<Token: Debug, Value: From<Token> + Default> Driver<Token, Value> {
+    /// A driver in the start state of `tables`.
+    pub fn new(tables: &'static Tables<Token, Value>) -> Self {
+        Self {
+            tables,
+            states: vec![0],
+            values: Vec::new(),
+            errors: Vec::new(),
+            recovering: false,
+            skipping: false,
+            end: Location::default(),
+        }
+    }
+
+    /// Shift a token, reducing first as the tables require, or record an error and
+    /// skip tokens until a sync terminal can resume parsing.
+    pub fn process(&mut self, token: Span<Token>) {
+        self.end = token.end;
+
+        let Some(terminal) = (self.tables.terminal)(&token.data) else {
+            self.error(token.swap(Some(format!("`{:?}`", token.data))));
+            self.skip(token.end);
+            return;
+        };
+
+        if !self.recovering {
+            if let Some(Action::Shift(state)) = self.advance(terminal, token.start) {
+                self.shift(state, token);
+                return;
+            }
+
+            self.error(token.swap(Some(format!("`{:?}`", token.data))));
+        }
+
+        if self.resync(terminal) {
+            self.skipping = false;
+
+            if let Some(Action::Shift(state)) = self.advance(terminal, token.start) {
+                self.recovering = false;
+                self.shift(state, token);
+                return;
+            }
+        }
+
+        self.skip(token.end);
+    }
+
+    /// The value if the input was accepted without errors.
+    pub fn finish(self) -> Result<Value, ParseErrors<SyntaxError>> {
+        match self.finish_partial() {
+            (Some(value), errors) if errors.is_empty() => Ok(value),
+            (_, errors) => Err(errors.into()),
+        }
+    }
+
+    /// The value with any error nodes in it, if parsing got that far, and the errors.
+    pub fn finish_partial(mut self) -> (Option<Value>, Vec<SyntaxError>) {
+        let end = self.tables.terminals.len() - 1;
+
+        if !self.recovering {
+            if let Some(Action::Accept) = self.advance(end, self.end) {
+                return (self.values.pop().map(|value| value.data), self.errors);
+            }
+
+            self.error(Span::new(self.end, self.end, None));
+        }
+
+        if self.skipping && self.action(self.state(), end).is_some() {
+            if let Some(Action::Accept) = self.advance(end, self.end) {
+                return (self.values.pop().map(|value| value.data), self.errors);
+            }
+        }
+
+        (None, self.errors)
+    }
+
+    fn state(&self) -> usize {
+        self.states.last().copied().unwrap_or(0)
+    }
+
+    fn action(&self, state: usize, terminal: usize) -> Option<Action> {
+        let actions = self.tables.actions[state];
+
+        actions
+            .binary_search_by_key(&terminal, |(terminal, _)| *terminal)
+            .ok()
+            .map(|index| actions[index].1)
+    }
+
+    fn advance(&mut self, terminal: usize, location: Location) -> Option<Action> {
+        loop {
+            match self.action(self.state(), terminal) {
+                Some(Action::Reduce(production)) => self.reduce(production, location),
+                action => return action,
+            }
+        }
+    }
+
+    fn shift(&mut self, state: usize, token: Span<Token>) {
+        self.states.push(state);
+        self.values.push(token.map(Value::from));
+    }
+
+    fn resync(&mut self, terminal: usize) -> bool {
+        if self.skipping && self.action(self.state(), terminal).is_some() {
+            return true;
+        }
+
+        if !self.tables.sync.contains(&terminal) {
+            return false;
+        }
+
+        let Some(depth) = self
+            .states
+            .iter()
+            .rposition(|&state| self.action(state, terminal).is_some())
+        else {
+            return false;
+        };
+
+        self.states.truncate(depth + 1);
+        self.values.truncate(depth);
+        true
+    }
+
+    fn skip(&mut self, end: Location) {
+        if let (true, Some(error)) = (self.skipping, self.values.last_mut()) {
+            error.end = end;
+        }
+    }
+
+    fn reduce(&mut self, production: usize, location: Location) {
+        let (nonterminal, length) = self.tables.productions[production];
+
+        let values = self.values.split_off(self.values.len() - length);
+        self.states.truncate(self.states.len() - length);
+
+        let span = match (values.first(), values.last()) {
+            (Some(first), Some(last)) => Span::new(first.start, last.end, ()),
+            _ => Span::new(location, location, ()),
+        };
+
+        let gotos = self.tables.gotos[self.state()];
+
+        let next = gotos
+            .binary_search_by_key(&nonterminal, |(nonterminal, _)| *nonterminal)
+            .map(|index| gotos[index].1)
+            .expect("generated tables have a goto for every reduction");
+
+        self.states.push(next);
+        self.values
+            .push(span.swap((self.tables.reduce)(production, values, span)));
+    }
+
+    fn error(&mut self, found: Span<Option<String>>) {
+        if std::mem::replace(&mut self.recovering, true) {
+            return;
+        }
+
+        let (error, end) = (
+            self.tables.terminals.len() - 2,
+            self.tables.terminals.len() - 1,
+        );
+
+        let expected = self.tables.actions[self.state()]
+            .iter()
+            .filter(|(terminal, _)| *terminal != error)
+            .map(|(terminal, _)| {
+                let name = self.tables.terminals[*terminal];
+
+                if *terminal == end {
+                    name.to_owned()
+                } else {
+                    format!("`{name}`")
+                }
+            })
+            .collect();
+
+        self.errors.push(SyntaxError {
+            span: found.swap(()),
+            expected,
+            found: found.data,
+        });
+
+        let Some((depth, next)) =
+            self.states
+                .iter()
+                .enumerate()
+                .rev()
+                .find_map(|(depth, &state)| match self.action(state, error) {
+                    Some(Action::Shift(next)) => Some((depth, next)),
+                    _ => None,
+                })
+        else {
+            return;
+        };
+
+        let span = match (self.values.get(depth), self.values.last()) {
+            (Some(first), Some(last)) => Span::new(first.start, last.end, ()),
+            _ => Span::new(found.start, found.start, ()),
+        };
+
+        self.states.truncate(depth + 1);
+        self.values.truncate(depth);
+
+        self.states.push(next);
+        self.values.push(span.swap(Value::default()));
+        self.skipping = true;
+    }
+}
@@ -1,19 +1,34 @@
 use std::{
     fs::File,
-    io::{BufRead, BufReader, Cursor},
-    path::Path,
+    io::{BufRead, BufReader, Cursor, Read, StdinLock},
+    path::{Path, PathBuf},
 };
 
+use ruinous_util::source::{SharedBuffer, SourceMap};
+
 use self::state::State as ParserState;
 use crate::{
     lexer::{state::State as LexerState, Lexer},
     reader::error::FileError,
 };
 
-pub use self::error::{Error, ParseErrors};
+pub use ruinous_macros::grammar;
+
+pub use self::error::{Error, ParseErrors, SyntaxError};
+
+#[cfg(feature = "async")]
+pub use self::asynchronous::AsyncParser;
 
+pub mod combinator;
+pub mod incremental;
+pub mod lalr;
+pub mod pratt;
+pub mod recovery;
 pub mod state;
+pub mod stream;
 
+#[cfg(feature = "async")]
+mod asynchronous;
 mod error;
 
 pub struct Parser<R> {
@@ -25,6 +40,41 @@ impl Parser<BufReader<File>> {
         let lexer = Lexer::with_file(path)?;
         Ok(Self { lexer })
     }
+
+    pub fn with_file_in<P: AsRef<Path>>(
+        sources: &mut SourceMap,
+        path: P,
+    ) -> Result<Self, FileError> {
+        let lexer = Lexer::with_file_in(sources, path)?;
+        Ok(Self { lexer })
+    }
+}
+
+impl Parser<StdinLock<'static>> {
+    pub fn with_stdin() -> Self {
+        let lexer = Lexer::with_stdin();
+        Self { lexer }
+    }
+}
+
+impl<R: Read> Parser<BufReader<R>> {
+    pub fn with_reader<N: Into<PathBuf>>(name: N, reader: R) -> Self {
+        let lexer = Lexer::with_reader(name, reader);
+        Self { lexer }
+    }
+}
+
+impl Parser<Cursor<SharedBuffer>> {
+    pub fn with_buffer<B: Into<SharedBuffer>>(buffer: B) -> Self {
+        let lexer = Lexer::with_buffer(buffer);
+        Self { lexer }
+    }
+
+    #[cfg(feature = "mmap")]
+    pub fn with_mmap<P: AsRef<Path>>(path: P) -> Result<Self, FileError> {
+        let lexer = Lexer::with_mmap(path)?;
+        Ok(Self { lexer })
+    }
 }
 
 impl<'a> Parser<Cursor<&'a str>> {
@@ -40,10 +90,7 @@ impl<R: BufRead> Parser<R> {
         lexer: LS,
         mut parser: PS,
     ) -> Result<PS::Ast, Error<LS, PS>> {
-        self.lexer
-            .lex(lexer, |token| parser.process(token))
-            .map_err(Error::LexError)?;
-
-        parser.finish().map_err(Error::ParseErrors)
+        let lexed = self.lexer.lex(lexer, |token| parser.process(token));
+        Error::finish(lexed, parser)
     }
 }
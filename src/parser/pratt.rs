@@ -0,0 +1,571 @@
+use std::{fmt::Debug, iter::Peekable};
+
+use ruinous_util::{
+    error::{context::ErrorProvider, writer::ErrorWriter},
+    span::{Location, Span},
+};
+
+use super::{
+    error::ParseErrors,
+    recovery::{Recovery, Resume},
+    state::State,
+};
+
+pub type Atom<Token, Expr> = dyn Fn(&Token) -> Option<Expr> + Send + Sync;
+pub type Prefix<Token, Expr> = dyn Fn(Span<Token>, Span<Expr>) -> Expr + Send + Sync;
+pub type Infix<Token, Expr> = dyn Fn(Span<Expr>, Span<Token>, Span<Expr>) -> Expr + Send + Sync;
+pub type Postfix<Token, Expr> = dyn Fn(Span<Expr>, Span<Token>) -> Expr + Send + Sync;
+pub type ErrorNode<Expr> = dyn Fn(Span<()>) -> Expr + Send + Sync;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Associativity {
+    Left,
+    Right,
+    None,
+}
+
+/// An operator precedence parser, where higher binding powers bind more tightly.
+pub struct Pratt<Token, Expr> {
+    atom: Box<Atom<Token, Expr>>,
+    prefix: Vec<Operator<Token, u32, Prefix<Token, Expr>>>,
+    infix: Vec<Operator<Token, Binding, Infix<Token, Expr>>>,
+    postfix: Vec<Operator<Token, u32, Postfix<Token, Expr>>>,
+    groups: Vec<(Token, Token)>,
+    recovery: Option<(Recovery<Token>, Box<ErrorNode<Expr>>)>,
+}
+
+pub struct PrattState<Token, Expr> {
+    pratt: Pratt<Token, Expr>,
+    tokens: Vec<Span<Token>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PrattError {
+    MissingOperand(Span<Option<String>>),
+    NonAssociative(Span<()>),
+    Unclosed {
+        close: String,
+        found: Span<Option<String>>,
+    },
+    Unexpected(Span<String>),
+}
+
+#[derive(Clone, Copy)]
+struct Binding(u32, Associativity);
+
+struct Operator<Token, Power, Constructor: ?Sized> {
+    token: Token,
+    power: Power,
+    constructor: Box<Constructor>,
+}
+
+impl<Token: PartialEq + Debug, Expr> Pratt<Token, Expr> {
+    pub fn new<F: Fn(&Token) -> Option<Expr> + Send + Sync + 'static>(atom: F) -> Self {
+        Self {
+            atom: Box::new(atom),
+            prefix: Vec::new(),
+            infix: Vec::new(),
+            postfix: Vec::new(),
+            groups: Vec::new(),
+            recovery: None,
+        }
+    }
+
+    #[must_use]
+    pub fn prefix<F: Fn(Span<Token>, Span<Expr>) -> Expr + Send + Sync + 'static>(
+        mut self,
+        token: Token,
+        power: u32,
+        constructor: F,
+    ) -> Self {
+        self.prefix.push(Operator {
+            token,
+            power,
+            constructor: Box::new(constructor),
+        });
+        self
+    }
+
+    #[must_use]
+    pub fn infix<F: Fn(Span<Expr>, Span<Token>, Span<Expr>) -> Expr + Send + Sync + 'static>(
+        mut self,
+        token: Token,
+        power: u32,
+        associativity: Associativity,
+        constructor: F,
+    ) -> Self {
+        self.infix.push(Operator {
+            token,
+            power: Binding(power, associativity),
+            constructor: Box::new(constructor),
+        });
+        self
+    }
+
+    #[must_use]
+    pub fn postfix<F: Fn(Span<Expr>, Span<Token>) -> Expr + Send + Sync + 'static>(
+        mut self,
+        token: Token,
+        power: u32,
+        constructor: F,
+    ) -> Self {
+        self.postfix.push(Operator {
+            token,
+            power,
+            constructor: Box::new(constructor),
+        });
+        self
+    }
+
+    /// Allow `open` and `close` around an expression to override precedence.
+    #[must_use]
+    pub fn group(mut self, open: Token, close: Token) -> Self {
+        self.groups.push((open, close));
+        self
+    }
+
+    /// Skip to a sync point after an error, and put a node built by `node` from the
+    /// skipped span in place of the operand that couldn't be parsed.
+    #[must_use]
+    pub fn recover<F: Fn(Span<()>) -> Expr + Send + Sync + 'static>(
+        mut self,
+        recovery: Recovery<Token>,
+        node: F,
+    ) -> Self {
+        self.recovery = Some((recovery, Box::new(node)));
+        self
+    }
+
+    pub fn state(self) -> PrattState<Token, Expr> {
+        PrattState {
+            pratt: self,
+            tokens: Vec::new(),
+        }
+    }
+
+    /// Parse an expression from the front of `tokens`, where `end` is where the input
+    /// finishes. Errors that were recovered from are pushed onto `errors`.
+    pub fn parse<I: Iterator<Item = Span<Token>>>(
+        &self,
+        tokens: &mut Peekable<I>,
+        end: Location,
+        errors: &mut Vec<PrattError>,
+    ) -> Result<Span<Expr>, PrattError> {
+        self.expression(tokens, end, 0, errors)
+    }
+
+    fn expression<I: Iterator<Item = Span<Token>>>(
+        &self,
+        tokens: &mut Peekable<I>,
+        end: Location,
+        min: u32,
+        errors: &mut Vec<PrattError>,
+    ) -> Result<Span<Expr>, PrattError> {
+        let mut lhs = match self.operand(tokens, end, errors) {
+            Ok(lhs) => lhs,
+            Err(error) => self.recovered(tokens, end, error, errors)?,
+        };
+
+        let mut chained: Option<(u32, Location)> = None;
+
+        while let Some(token) = tokens.peek() {
+            if let Some(operator) = self.postfix.iter().find(|op| op.token == token.data) {
+                if operator.power * 2 < min {
+                    break;
+                }
+
+                let token = tokens.next().expect("token was peeked");
+                let span = Span::new(lhs.start, token.end, ());
+                lhs = span.swap((operator.constructor)(lhs, token));
+                continue;
+            }
+
+            let Some(operator) = self.infix.iter().find(|op| op.token == token.data) else {
+                break;
+            };
+
+            let Binding(power, associativity) = operator.power;
+
+            let (left, right) = match associativity {
+                Associativity::Right => (power * 2 + 1, power * 2),
+                Associativity::Left | Associativity::None => (power * 2, power * 2 + 1),
+            };
+
+            if left < min {
+                break;
+            }
+
+            if associativity == Associativity::None {
+                if let Some((_, first)) = chained.filter(|(chained, _)| *chained == power) {
+                    let span = Span::new(first, token.end, ());
+                    self.report(PrattError::NonAssociative(span), errors)?;
+                }
+
+                chained = Some((power, token.start));
+            }
+
+            let token = tokens.next().expect("token was peeked");
+            let rhs = self.expression(tokens, end, right, errors)?;
+            let span = Span::new(lhs.start, rhs.end, ());
+            lhs = span.swap((operator.constructor)(lhs, token, rhs));
+        }
+
+        Ok(lhs)
+    }
+
+    fn operand<I: Iterator<Item = Span<Token>>>(
+        &self,
+        tokens: &mut Peekable<I>,
+        end: Location,
+        errors: &mut Vec<PrattError>,
+    ) -> Result<Span<Expr>, PrattError> {
+        let Some(token) = tokens.peek() else {
+            return Err(PrattError::MissingOperand(Span::new(end, end, None)));
+        };
+
+        if let Some(operator) = self.prefix.iter().find(|op| op.token == token.data) {
+            let token = tokens.next().expect("token was peeked");
+            let rhs = self.expression(tokens, end, operator.power * 2, errors)?;
+            let span = Span::new(token.start, rhs.end, ());
+            return Ok(span.swap((operator.constructor)(token, rhs)));
+        }
+
+        if let Some((_, close)) = self.groups.iter().find(|(open, _)| *open == token.data) {
+            let open = tokens.next().expect("token was peeked");
+            let inner = self.expression(tokens, end, 0, errors)?;
+
+            if let Some(last) = tokens.next_if(|token| token.data == *close) {
+                return Ok(Span::new(open.start, last.end, inner.data));
+            }
+
+            let error = PrattError::Unclosed {
+                close: format!("`{close:?}`"),
+                found: describe(tokens.peek(), end),
+            };
+
+            self.report(error, errors)?;
+            return Ok(Span::new(open.start, inner.end, inner.data));
+        }
+
+        match (self.atom)(&token.data) {
+            Some(expr) => Ok(tokens.next().expect("token was peeked").swap(expr)),
+            None => Err(PrattError::MissingOperand(describe(Some(token), end))),
+        }
+    }
+
+    fn report(&self, error: PrattError, errors: &mut Vec<PrattError>) -> Result<(), PrattError> {
+        if self.recovery.is_some() {
+            errors.push(error);
+            Ok(())
+        } else {
+            Err(error)
+        }
+    }
+
+    fn recovered<I: Iterator<Item = Span<Token>>>(
+        &self,
+        tokens: &mut Peekable<I>,
+        end: Location,
+        error: PrattError,
+        errors: &mut Vec<PrattError>,
+    ) -> Result<Span<Expr>, PrattError> {
+        let Some((recovery, node)) = &self.recovery else {
+            return Err(error);
+        };
+
+        errors.push(error);
+
+        let start = tokens.peek().map_or(end, |token| token.start);
+        let mut span = Span::new(start, start, ());
+
+        while let Some(token) = tokens.peek() {
+            let resume = recovery.resume(&token.data);
+
+            if resume == Some(Resume::Boundary) {
+                break;
+            }
+
+            span.end = tokens.next().expect("token was peeked").end;
+
+            if resume == Some(Resume::Terminator) {
+                break;
+            }
+        }
+
+        Ok(span.swap(node(span)))
+    }
+}
+
+fn describe<Token: Debug>(token: Option<&Span<Token>>, end: Location) -> Span<Option<String>> {
+    match token {
+        Some(token) => token.swap(Some(format!("`{:?}`", token.data))),
+        None => Span::new(end, end, None),
+    }
+}
+
+impl<Token: PartialEq + Debug, Expr> State<Token> for PrattState<Token, Expr> {
+    type Ast = Span<Expr>;
+    type Error = PrattError;
+
+    fn process(&mut self, token: Span<Token>) {
+        self.tokens.push(token);
+    }
+
+    fn finish(self) -> Result<Self::Ast, ParseErrors<Self::Error>> {
+        let end = self
+            .tokens
+            .last()
+            .map_or(Location::default(), |token| token.end);
+
+        let mut tokens = self.tokens.into_iter().peekable();
+        let mut errors = Vec::new();
+
+        match self.pratt.parse(&mut tokens, end, &mut errors) {
+            Ok(expr) => {
+                if let Some(token) = tokens.next() {
+                    errors.push(PrattError::Unexpected(
+                        token.map(|token| format!("`{token:?}`")),
+                    ));
+                }
+
+                if errors.is_empty() {
+                    Ok(expr)
+                } else {
+                    Err(errors.into())
+                }
+            }
+            Err(error) => {
+                errors.push(error);
+                Err(errors.into())
+            }
+        }
+    }
+}
+
+impl ErrorProvider for PrattError {
+    fn write_errors(&self, writer: &mut dyn ErrorWriter) -> std::fmt::Result {
+        let found = |span: &Span<Option<String>>| {
+            span.data
+                .clone()
+                .unwrap_or_else(|| "end of input".to_owned())
+        };
+
+        match self {
+            PrattError::MissingOperand(span) => writer.error(
+                Some(span.swap(())),
+                &format!("Expected an operand, found {}", found(span)),
+            ),
+            PrattError::NonAssociative(span) => writer.error(
+                Some(*span),
+                "Operators of this precedence cannot be chained",
+            ),
+            PrattError::Unclosed { close, found: span } => writer.error(
+                Some(span.swap(())),
+                &format!("Expected {close} to close group, found {}", found(span)),
+            ),
+            PrattError::Unexpected(span) => writer.error(
+                Some(span.swap(())),
+                &format!("Unexpected {} after expression", span.data),
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        lexer::{
+            rules::{RuleLexer, Rules},
+            Lexer,
+        },
+        parser::{Error, Parser},
+    };
+
+    #[derive(Debug, Clone, PartialEq)]
+    enum Token {
+        Number(u64),
+        Op(char),
+    }
+
+    fn binary(op: char, lhs: Span<String>, rhs: Span<String>) -> String {
+        format!("({op} {} {})", lhs.data, rhs.data)
+    }
+
+    fn lexer() -> RuleLexer<Token> {
+        Rules::new()
+            .regex(r"[-+*^!<()]", |text| {
+                Token::Op(text.chars().next().unwrap())
+            })
+            .and_then(|rules| rules.regex(r"\d+", |text| Token::Number(text.parse().unwrap())))
+            .and_then(|rules| rules.skip(r"\s+"))
+            .unwrap()
+            .build()
+    }
+
+    fn pratt() -> Pratt<Token, String> {
+        Pratt::new(|token| match token {
+            Token::Number(number) => Some(number.to_string()),
+            Token::Op(_) => None,
+        })
+        .infix(Token::Op('<'), 1, Associativity::None, |lhs, _, rhs| {
+            binary('<', lhs, rhs)
+        })
+        .infix(Token::Op('+'), 2, Associativity::Left, |lhs, _, rhs| {
+            binary('+', lhs, rhs)
+        })
+        .infix(Token::Op('-'), 2, Associativity::Left, |lhs, _, rhs| {
+            binary('-', lhs, rhs)
+        })
+        .infix(Token::Op('*'), 3, Associativity::Left, |lhs, _, rhs| {
+            binary('*', lhs, rhs)
+        })
+        .infix(Token::Op('^'), 4, Associativity::Right, |lhs, _, rhs| {
+            binary('^', lhs, rhs)
+        })
+        .prefix(Token::Op('-'), 5, |_, rhs| format!("(- {})", rhs.data))
+        .postfix(Token::Op('!'), 6, |lhs, _| format!("(! {})", lhs.data))
+        .group(Token::Op('('), Token::Op(')'))
+    }
+
+    fn parse(input: &str) -> Result<Span<String>, PrattError> {
+        match Parser::with_str(input).parse(lexer(), pratt().state()) {
+            Ok(expr) => Ok(expr),
+            Err(Error::ParseErrors(mut errors)) => Err(errors.errors.remove(0)),
+            Err(error) => panic!("{error:?}"),
+        }
+    }
+
+    #[test]
+    fn pratt_binding_powers() {
+        let expr = |input| parse(input).unwrap().data;
+
+        assert_eq!(expr("1 + 2 * 3"), "(+ 1 (* 2 3))");
+        assert_eq!(expr("1 - 2 - 3"), "(- (- 1 2) 3)");
+        assert_eq!(expr("2 ^ 3 ^ 2"), "(^ 2 (^ 3 2))");
+        assert_eq!(expr("-1 * 2!"), "(* (- 1) (! 2))");
+        assert_eq!(expr("-2 ^ 2"), "(^ (- 2) 2)");
+        assert_eq!(expr("1 < 2 + 3"), "(< 1 (+ 2 3))");
+
+        let expr = parse("(1 + 2) * 3").unwrap();
+        assert_eq!(expr.data, "(* (+ 1 2) 3)");
+        assert_eq!(expr.range(), 0..11);
+
+        assert_eq!(parse(" -1!").unwrap().range(), 1..4);
+    }
+
+    #[test]
+    fn pratt_errors() {
+        let PrattError::MissingOperand(span) = parse("1 +").unwrap_err() else {
+            panic!();
+        };
+        assert_eq!((span.range(), span.data), (3..3, None));
+
+        let PrattError::MissingOperand(span) = parse("1 * * 2").unwrap_err() else {
+            panic!();
+        };
+        assert_eq!(
+            (span.range(), span.data.as_deref()),
+            (4..5, Some("`Op('*')`"))
+        );
+
+        let PrattError::NonAssociative(span) = parse("1 < 2 < 3").unwrap_err() else {
+            panic!();
+        };
+        assert_eq!(span.range(), 2..7);
+
+        let PrattError::Unclosed { close, found } = parse("(1 + 2").unwrap_err() else {
+            panic!();
+        };
+        assert_eq!(
+            (close.as_str(), found.range(), found.data),
+            ("`Op(')')`", 6..6, None)
+        );
+
+        let PrattError::Unexpected(span) = parse("1 2").unwrap_err() else {
+            panic!();
+        };
+        assert_eq!((span.range(), span.data.as_str()), (2..3, "`Number(2)`"));
+    }
+
+    #[test]
+    fn pratt_recovers_with_error_nodes() {
+        let recovering = || {
+            pratt().recover(Recovery::new().boundary(Token::Op(')')), |span| {
+                format!("<error {:?}>", span.range())
+            })
+        };
+
+        let tokens = Lexer::with_str("(1 + ) * (2 * * 3) + 4")
+            .tokens(lexer())
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        let mut errors = Vec::new();
+        let expr = recovering()
+            .parse(
+                &mut tokens.into_iter().peekable(),
+                Location::default(),
+                &mut errors,
+            )
+            .unwrap();
+
+        assert_eq!(
+            expr.data,
+            "(+ (* (+ 1 <error 5..5>) (* 2 <error 14..17>)) 4)"
+        );
+
+        let missing = |error: &PrattError| match error {
+            PrattError::MissingOperand(span) => Some((span.range(), span.data.clone())),
+            _ => None,
+        };
+
+        assert_eq!(
+            errors.iter().map(missing).collect::<Vec<_>>(),
+            [
+                Some((5..6, Some("`Op(')')`".to_owned()))),
+                Some((14..15, Some("`Op('*')`".to_owned()))),
+            ]
+        );
+
+        let Err(Error::ParseErrors(errors)) =
+            Parser::with_str("1 < 2 < 3 + * 4").parse(lexer(), recovering().state())
+        else {
+            panic!("expected every error to be reported");
+        };
+
+        assert_eq!(errors.errors.len(), 2);
+        assert!(
+            matches!(&errors.errors[0], PrattError::NonAssociative(span) if span.range() == (2..7))
+        );
+        assert_eq!(
+            missing(&errors.errors[1]),
+            Some((12..13, Some("`Op('*')`".to_owned())))
+        );
+    }
+
+    #[test]
+    fn pratt_errors_are_reported_with_lex_errors() {
+        let result = Parser::with_str("1 + $ * 2").parse(lexer(), pratt().state());
+
+        let Err(Error::LexAndParseErrors(lex, parse)) = result else {
+            panic!("expected lex and parse errors, got {result:?}");
+        };
+
+        assert_eq!(lex.errors.len(), 1);
+
+        let PrattError::MissingOperand(span) = &parse.errors[0] else {
+            panic!("{:?}", parse.errors);
+        };
+        assert_eq!(span.range(), 6..7);
+
+        let result = Parser::with_str("1 + $2 * 3").parse(lexer(), pratt().state());
+
+        let Err(Error::LexErrorsWithAst(lex, ast)) = result else {
+            panic!("expected lex errors with an AST, got {result:?}");
+        };
+
+        assert_eq!(lex.errors.len(), 1);
+        assert_eq!(ast.data, "(+ 1 (* 2 3))");
+    }
+}
@@ -0,0 +1,55 @@
+use std::sync::Arc;
+
+use super::stream::Kind;
+
+/// The tokens a parser can pick up from again after an error.
+pub struct Recovery<Token> {
+    points: Vec<(Arc<dyn Kind<Token> + Send + Sync>, Resume)>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Resume {
+    /// Skipped along with the broken construct, like `;`.
+    Terminator,
+    /// Parsed as part of whatever comes next, like `}` or a keyword.
+    Boundary,
+}
+
+impl<Token> Recovery<Token> {
+    pub fn new() -> Self {
+        Self { points: Vec::new() }
+    }
+
+    #[must_use]
+    pub fn terminator<K: Kind<Token> + Send + Sync + 'static>(mut self, kind: K) -> Self {
+        self.points.push((Arc::new(kind), Resume::Terminator));
+        self
+    }
+
+    #[must_use]
+    pub fn boundary<K: Kind<Token> + Send + Sync + 'static>(mut self, kind: K) -> Self {
+        self.points.push((Arc::new(kind), Resume::Boundary));
+        self
+    }
+
+    pub fn resume(&self, token: &Token) -> Option<Resume> {
+        self.points
+            .iter()
+            .find(|(kind, _)| kind.matches(token))
+            .map(|(_, resume)| *resume)
+    }
+}
+
+impl<Token> Default for Recovery<Token> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<Token> Clone for Recovery<Token> {
+    fn clone(&self) -> Self {
+        Self {
+            points: self.points.clone(),
+        }
+    }
+}
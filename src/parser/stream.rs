@@ -0,0 +1,367 @@
+use std::fmt::Debug;
+
+use ruinous_util::span::{Location, Span};
+
+use super::{
+    error::{ParseErrors, SyntaxError},
+    recovery::{Recovery, Resume},
+};
+
+/// A cursor over tokens for hand-written recursive descent parsers, which can be
+/// rewound to a [`Checkpoint`].
+pub struct TokenStream<Token, I> {
+    tokens: I,
+    buffer: Vec<Span<Token>>,
+    position: usize,
+    end: Location,
+    expected: Vec<String>,
+    errors: Vec<SyntaxError>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Checkpoint(usize);
+
+/// Something tokens can be checked against, with a description for errors.
+pub trait Kind<Token> {
+    fn matches(&self, token: &Token) -> bool;
+    fn description(&self) -> String;
+}
+
+pub struct Predicate<F> {
+    description: String,
+    predicate: F,
+}
+
+/// Matches tokens that `predicate` accepts, described as `description` in errors.
+pub fn kind<Token, F: Fn(&Token) -> bool>(
+    description: impl Into<String>,
+    predicate: F,
+) -> Predicate<F> {
+    Predicate {
+        description: description.into(),
+        predicate,
+    }
+}
+
+impl<Token: Clone + Debug, I: Iterator<Item = Span<Token>>> TokenStream<Token, I> {
+    pub fn new<T: IntoIterator<IntoIter = I>>(tokens: T) -> Self {
+        Self {
+            tokens: tokens.into_iter(),
+            buffer: Vec::new(),
+            position: 0,
+            end: Location::default(),
+            expected: Vec::new(),
+            errors: Vec::new(),
+        }
+    }
+
+    pub fn peek(&mut self, n: usize) -> Option<&Span<Token>> {
+        while self.buffer.len() <= self.position + n {
+            let token = self.tokens.next()?;
+            self.end = token.end;
+            self.buffer.push(token);
+        }
+
+        self.buffer.get(self.position + n)
+    }
+
+    pub fn bump(&mut self) -> Option<Span<Token>> {
+        let token = self.peek(0)?.clone();
+        self.position += 1;
+        self.expected.clear();
+        Some(token)
+    }
+
+    pub fn at_end(&mut self) -> bool {
+        self.peek(0).is_none()
+    }
+
+    /// A `false` result is remembered, so that a later error here expects `kind`.
+    pub fn at<K: Kind<Token>>(&mut self, kind: K) -> bool {
+        match self.peek(0) {
+            Some(token) if kind.matches(&token.data) => true,
+            _ => {
+                self.expected(kind.description());
+                false
+            }
+        }
+    }
+
+    pub fn eat<K: Kind<Token>>(&mut self, kind: K) -> Option<Span<Token>> {
+        if self.at(kind) {
+            self.bump()
+        } else {
+            None
+        }
+    }
+
+    pub fn expect<K: Kind<Token>>(&mut self, kind: K) -> Result<Span<Token>, SyntaxError> {
+        match self.eat(kind) {
+            Some(token) => Ok(token),
+            None => Err(self.error()),
+        }
+    }
+
+    pub fn expected<D: Into<String>>(&mut self, description: D) {
+        let description = description.into();
+
+        if !self.expected.contains(&description) {
+            self.expected.push(description);
+        }
+    }
+
+    /// The error for the current position, expecting everything checked for here.
+    pub fn error(&mut self) -> SyntaxError {
+        let found = self
+            .peek(0)
+            .map(|token| token.swap(format!("`{:?}`", token.data)));
+
+        SyntaxError {
+            span: found
+                .as_ref()
+                .map_or(Span::new(self.end, self.end, ()), |found| found.swap(())),
+            expected: self.expected.clone(),
+            found: found.map(|found| found.data),
+        }
+    }
+
+    /// Record `error` and skip to the next sync point, returning the span from `start`
+    /// to build an error node from. At least one token is always skipped.
+    pub fn recover(
+        &mut self,
+        start: Checkpoint,
+        error: SyntaxError,
+        recovery: &Recovery<Token>,
+    ) -> Span<()> {
+        self.errors.push(error);
+
+        while let Some(token) = self.peek(0) {
+            match recovery.resume(&token.data) {
+                Some(Resume::Boundary) if self.position > start.0 => break,
+                Some(Resume::Terminator) => {
+                    self.bump();
+                    break;
+                }
+                _ => {
+                    self.bump();
+                }
+            }
+        }
+
+        self.span(start)
+    }
+
+    pub fn errors(&self) -> &[SyntaxError] {
+        &self.errors
+    }
+
+    pub fn finish<Ast>(self, ast: Ast) -> Result<Ast, ParseErrors<SyntaxError>> {
+        if self.errors.is_empty() {
+            Ok(ast)
+        } else {
+            Err(self.errors.into())
+        }
+    }
+
+    pub fn checkpoint(&self) -> Checkpoint {
+        Checkpoint(self.position)
+    }
+
+    pub fn rewind(&mut self, checkpoint: Checkpoint) {
+        self.position = checkpoint.0;
+        self.expected.clear();
+    }
+
+    pub fn span(&mut self, checkpoint: Checkpoint) -> Span<()> {
+        let end = self.end;
+
+        let start = match self.buffer.get(checkpoint.0) {
+            Some(token) => token.start,
+            None => self.peek(0).map_or(end, |token| token.start),
+        };
+
+        let end = match self.position.checked_sub(1) {
+            Some(last) if last >= checkpoint.0 => self.buffer[last].end,
+            _ => start,
+        };
+
+        Span::new(start, end, ())
+    }
+}
+
+impl<Token: PartialEq + Debug> Kind<Token> for Token {
+    fn matches(&self, token: &Token) -> bool {
+        self == token
+    }
+
+    fn description(&self) -> String {
+        format!("`{self:?}`")
+    }
+}
+
+impl<Token, F: Fn(&Token) -> bool> Kind<Token> for Predicate<F> {
+    fn matches(&self, token: &Token) -> bool {
+        (self.predicate)(token)
+    }
+
+    fn description(&self) -> String {
+        self.description.clone()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        io::Cursor,
+        sync::{Arc, Mutex},
+    };
+
+    use ruinous_util::error::context::{ErrorContext, PackagedError};
+
+    use super::*;
+    use crate::lexer::{
+        rules::{RuleLexer, Rules},
+        Lexer,
+    };
+
+    #[derive(Debug, Clone, PartialEq)]
+    enum Token {
+        Let,
+        Ident(String),
+        Number(u64),
+        Equals,
+        Semi,
+    }
+
+    fn lexer() -> RuleLexer<Token> {
+        Rules::new()
+            .literal("let", |_| Token::Let)
+            .literal("=", |_| Token::Equals)
+            .literal(";", |_| Token::Semi)
+            .regex("[a-z]+", |text| Token::Ident(text.to_owned()))
+            .and_then(|rules| rules.regex(r"\d+", |text| Token::Number(text.parse().unwrap())))
+            .and_then(|rules| rules.skip(r"\s+"))
+            .unwrap()
+            .build()
+    }
+
+    fn stream(input: &str) -> TokenStream<Token, std::vec::IntoIter<Span<Token>>> {
+        let tokens = Lexer::with_str(input)
+            .tokens(lexer())
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        TokenStream::new(tokens)
+    }
+
+    fn ident() -> Predicate<impl Fn(&Token) -> bool> {
+        kind("identifier", |token| matches!(token, Token::Ident(_)))
+    }
+
+    fn number() -> Predicate<impl Fn(&Token) -> bool> {
+        kind("number", |token| matches!(token, Token::Number(_)))
+    }
+
+    fn statement<I: Iterator<Item = Span<Token>>>(
+        tokens: &mut TokenStream<Token, I>,
+    ) -> Result<Span<Option<Token>>, SyntaxError> {
+        let start = tokens.checkpoint();
+
+        let name = match (tokens.eat(Token::Let), tokens.eat(ident())) {
+            (_, Some(name)) if tokens.eat(Token::Equals).is_some() => Some(name.data),
+            (None, _) => {
+                tokens.rewind(start);
+                None
+            }
+            (Some(_), _) => return Err(tokens.error()),
+        };
+
+        if tokens.eat(number()).is_none() {
+            tokens.expect(ident())?;
+        }
+
+        tokens.expect(Token::Semi)?;
+        Ok(tokens.span(start).swap(name))
+    }
+
+    #[test]
+    fn stream_looks_ahead_and_rewinds() {
+        let mut tokens = stream("let a = 1;\nb;");
+
+        assert_eq!(
+            tokens.peek(1).map(|token| &token.data),
+            Some(&Token::Ident("a".to_owned()))
+        );
+
+        let first = statement(&mut tokens).unwrap();
+        assert_eq!(first.data, Some(Token::Ident("a".to_owned())));
+        assert_eq!(first.range(), 0..10);
+
+        let checkpoint = tokens.checkpoint();
+        let second = statement(&mut tokens).unwrap();
+        assert_eq!(second.data, None);
+        assert_eq!(second.range(), 11..13);
+        assert!(tokens.at_end());
+
+        tokens.rewind(checkpoint);
+        assert_eq!(tokens.bump().map(|token| token.range()), Some(11..12));
+    }
+
+    #[test]
+    fn stream_reports_expected_tokens() {
+        let input = "let a = ;";
+        let error = statement(&mut stream(input)).unwrap_err();
+
+        assert_eq!(error.expected, ["number", "identifier"]);
+        assert_eq!(error.found.as_deref(), Some("`Semi`"));
+
+        let context = Arc::new(Mutex::new(ErrorContext::new(Cursor::new(input))));
+
+        assert_eq!(
+            PackagedError::new(context, error).to_string(),
+            "error: 0:8: Expected number or identifier, found `Semi`\n\n\t> let a = ;\n\t>         ^"
+        );
+
+        let error = statement(&mut stream("let a = 1")).unwrap_err();
+        assert_eq!(error.expected, ["`Semi`"]);
+        assert_eq!((error.found, error.span.range()), (None, 9..9));
+    }
+
+    #[test]
+    fn stream_recovers_at_sync_points() {
+        let recovery = Recovery::new().terminator(Token::Semi).boundary(Token::Let);
+
+        let mut tokens = stream("let a = ;\nlet b = 2;\nc d;\nlet e = 1 let f = 2;");
+        let mut statements = Vec::new();
+
+        while !tokens.at_end() {
+            let start = tokens.checkpoint();
+
+            statements.push(match statement(&mut tokens) {
+                Ok(statement) => Ok(statement.data),
+                Err(error) => Err(tokens.recover(start, error, &recovery).range()),
+            });
+        }
+
+        assert_eq!(
+            statements,
+            [
+                Err(0..9),
+                Ok(Some(Token::Ident("b".to_owned()))),
+                Err(21..25),
+                Err(26..35),
+                Ok(Some(Token::Ident("f".to_owned()))),
+            ]
+        );
+
+        let errors = tokens.finish(statements).unwrap_err().errors;
+
+        assert_eq!(
+            errors
+                .iter()
+                .map(|error| error.span.range())
+                .collect::<Vec<_>>(),
+            [8..9, 23..24, 36..39]
+        );
+    }
+}
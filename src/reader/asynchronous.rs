@@ -0,0 +1,140 @@
+use std::{io::ErrorKind, path::PathBuf};
+
+use futures_util::io::{AsyncBufRead, AsyncBufReadExt, AsyncRead, BufReader};
+
+use crate::{
+    source::SourceId,
+    span::{Columns, Span},
+};
+
+use super::{error::FileError, scanner::Scanner, Char, Continuation, Encoding, Line, LineEndings};
+
+pub struct AsyncCharReader<R> {
+    input: R,
+    scanner: Scanner,
+}
+
+impl<R: AsyncRead + Unpin> AsyncCharReader<BufReader<R>> {
+    pub fn with_reader<N: Into<PathBuf>>(name: N, reader: R) -> Self {
+        Self::new(BufReader::new(reader), Some(name.into()))
+    }
+}
+
+impl<R: AsyncBufRead + Unpin> AsyncCharReader<R> {
+    pub fn new(input: R, path: Option<PathBuf>) -> Self {
+        Self {
+            input,
+            scanner: Scanner::new(path),
+        }
+    }
+
+    #[must_use]
+    pub fn with_source(mut self, source: SourceId) -> Self {
+        self.scanner.set_source(source);
+        self
+    }
+
+    #[must_use]
+    pub fn lossless(mut self) -> Self {
+        self.scanner.set_lossless();
+        self
+    }
+
+    #[must_use]
+    pub fn encoding(mut self, encoding: Encoding) -> Self {
+        self.scanner.set_encoding(encoding);
+        self
+    }
+
+    #[must_use]
+    pub fn lossy(mut self) -> Self {
+        self.scanner.set_lossy();
+        self
+    }
+
+    #[must_use]
+    pub fn columns(mut self, columns: Columns) -> Self {
+        self.scanner.set_columns(columns);
+        self
+    }
+
+    pub async fn read<Callback: FnMut(Span<char>) -> Continuation>(
+        mut self,
+        mut callback: Callback,
+    ) -> Result<(), FileError> {
+        while let Some(char) = self.next_char().await? {
+            while let Continuation::Peek = callback(char) {}
+        }
+
+        Ok(())
+    }
+
+    pub fn endings(&self) -> &LineEndings {
+        self.scanner.endings()
+    }
+
+    pub async fn next_char(&mut self) -> Result<Option<Span<char>>, FileError> {
+        let char = self.next_event().await?;
+        Ok(char.map(|span| span.map(|char| char.char)))
+    }
+
+    pub async fn next_event(&mut self) -> Result<Option<Span<Char>>, FileError> {
+        self.fill().await?;
+        self.scanner.next_char()
+    }
+
+    pub async fn next_line(&mut self) -> Result<Option<Line<'_>>, FileError> {
+        self.fill().await?;
+        self.scanner.next_line()
+    }
+
+    async fn fill(&mut self) -> Result<(), FileError> {
+        while self.scanner.wants_input() {
+            let input = match self.input.fill_buf().await {
+                Ok(input) => input,
+                Err(err) if err.kind() == ErrorKind::Interrupted => continue,
+                Err(err) => return Err(self.scanner.read_error(err)),
+            };
+
+            let read = input.len();
+            self.scanner.feed(input);
+            self.input.consume_unpin(read);
+        }
+
+        Ok(())
+    }
+
+    pub async fn collect(self) -> Result<Vec<Span<char>>, FileError> {
+        let mut vec = Vec::new();
+
+        self.read(|c| {
+            vec.push(c);
+            Continuation::Consume
+        })
+        .await?;
+
+        Ok(vec)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use futures_executor::block_on;
+    use futures_util::io::Cursor;
+
+    use super::*;
+    use crate::reader::CharReader;
+
+    #[test]
+    fn async_read_matches_sync() {
+        let input = "fn main() {\r\n\tprint(\"🐉\");\r}\n";
+
+        let expected = CharReader::with_str(input).collect().unwrap();
+        let result = block_on(
+            AsyncCharReader::new(BufReader::with_capacity(3, Cursor::new(input)), None).collect(),
+        )
+        .unwrap();
+
+        assert_eq!(result, expected);
+    }
+}
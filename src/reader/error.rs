@@ -1,5 +1,9 @@
 use std::path::PathBuf;
 
+use crate::span::Span;
+
+use super::Encoding;
+
 #[derive(Debug)]
 pub enum FileError {
     FileOpen {
@@ -10,6 +14,16 @@ pub enum FileError {
         file: PathBuf,
         source: std::io::Error,
     },
+    InvalidEncoding {
+        file: PathBuf,
+        span: Span<()>,
+        encoding: Encoding,
+        bytes: Vec<u8>,
+    },
+    InvalidEdit {
+        span: Span<()>,
+        length: usize,
+    },
 }
 
 impl FileError {
@@ -22,4 +36,24 @@ impl FileError {
     pub fn file_read(path: PathBuf, source: std::io::Error) -> Self {
         FileError::FileRead { file: path, source }
     }
+
+    #[must_use]
+    pub fn invalid_encoding(
+        path: PathBuf,
+        span: Span<()>,
+        encoding: Encoding,
+        bytes: Vec<u8>,
+    ) -> Self {
+        FileError::InvalidEncoding {
+            file: path,
+            span,
+            encoding,
+            bytes,
+        }
+    }
+
+    #[must_use]
+    pub fn invalid_edit(span: Span<()>, length: usize) -> Self {
+        FileError::InvalidEdit { span, length }
+    }
 }
@@ -1,23 +1,134 @@
 use std::{
     fs::File,
-    io::{BufRead, BufReader, Cursor},
+    io::{self, BufRead, BufReader, Cursor, ErrorKind, Read, StdinLock},
     path::{Path, PathBuf},
 };
 
-use crate::span::{Location, Span};
+use crate::{
+    source::{SharedBuffer, SourceId, SourceMap},
+    span::{Columns, Location, Span},
+};
+
+use self::{error::FileError, scanner::Scanner};
 
-use self::error::FileError;
+pub use crate::encoding::Encoding;
+
+#[cfg(feature = "async")]
+pub use self::asynchronous::AsyncCharReader;
 
 pub mod error;
 
+#[cfg(feature = "async")]
+mod asynchronous;
+mod scanner;
+
 pub enum Continuation {
     Consume,
     Peek,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LineEnding {
+    Lf,
+    CrLf,
+    Cr,
+    Eof,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LineEndings {
+    pub lf: usize,
+    pub crlf: usize,
+    pub cr: usize,
+    pub missing_final_newline: bool,
+}
+
+/// A char from [`CharReader::next_event`], with the ending of its line if it is part of
+/// one. Outside of lossless mode each line ends in a single `'\n'`, which is synthetic
+/// when its ending is [`LineEnding::Eof`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Char {
+    pub char: char,
+    pub ending: Option<LineEnding>,
+}
+
+pub struct Line<'a> {
+    pub text: &'a str,
+    pub start: Location,
+    pub newline: Span<char>,
+    pub ending: LineEnding,
+    pub columns: Columns,
+}
+
+impl Char {
+    pub fn is_synthetic(self) -> bool {
+        self.ending.is_some_and(LineEnding::is_synthetic)
+    }
+}
+
+impl<'a> Line<'a> {
+    pub fn chars(&self) -> impl Iterator<Item = Span<char>> + '_ {
+        let mut location = self.start;
+
+        self.text.char_indices().map(move |(index, char)| {
+            let start = location;
+            let width = self.columns.width(self.text, index, location.col);
+
+            location.advance_by(char.len_utf8(), width);
+            Span::new(start, location, char)
+        })
+    }
+}
+
 pub struct CharReader<R> {
     input: R,
-    path: Option<PathBuf>,
+    scanner: Scanner,
+}
+
+impl LineEnding {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            LineEnding::Lf => "\n",
+            LineEnding::CrLf => "\r\n",
+            LineEnding::Cr => "\r",
+            LineEnding::Eof => "",
+        }
+    }
+
+    pub fn is_synthetic(self) -> bool {
+        self == LineEnding::Eof
+    }
+}
+
+impl LineEndings {
+    pub fn is_mixed(&self) -> bool {
+        [self.lf, self.crlf, self.cr]
+            .into_iter()
+            .filter(|&count| count > 0)
+            .count()
+            > 1
+    }
+
+    pub fn most_common(&self) -> Option<LineEnding> {
+        [
+            (self.lf, LineEnding::Lf),
+            (self.crlf, LineEnding::CrLf),
+            (self.cr, LineEnding::Cr),
+        ]
+        .into_iter()
+        .filter(|&(count, _)| count > 0)
+        .max_by_key(|&(count, _)| count)
+        .map(|(_, ending)| ending)
+    }
+
+    fn record(&mut self, ending: LineEnding) {
+        match ending {
+            LineEnding::Lf => self.lf += 1,
+            LineEnding::CrLf => self.crlf += 1,
+            LineEnding::Cr => self.cr += 1,
+            LineEnding::Eof => self.missing_final_newline = true,
+        }
+    }
 }
 
 impl CharReader<BufReader<File>> {
@@ -28,6 +139,47 @@ impl CharReader<BufReader<File>> {
 
         Ok(Self::new(reader, Some(path.to_owned())))
     }
+
+    pub fn with_file_in<P: AsRef<Path>>(
+        sources: &mut SourceMap,
+        path: P,
+    ) -> Result<Self, FileError> {
+        let reader = Self::with_file(&path)?;
+        Ok(reader.with_source(sources.add(path)))
+    }
+}
+
+impl CharReader<StdinLock<'static>> {
+    pub fn with_stdin() -> Self {
+        Self::new(io::stdin().lock(), Some(PathBuf::from("<stdin>")))
+    }
+}
+
+impl<R: Read> CharReader<BufReader<R>> {
+    pub fn with_reader<N: Into<PathBuf>>(name: N, reader: R) -> Self {
+        Self::new(BufReader::new(reader), Some(name.into()))
+    }
+}
+
+impl CharReader<Cursor<SharedBuffer>> {
+    pub fn with_buffer<B: Into<SharedBuffer>>(buffer: B) -> Self {
+        Self::shared(buffer.into(), None)
+    }
+
+    #[cfg(feature = "mmap")]
+    pub fn with_mmap<P: AsRef<Path>>(path: P) -> Result<Self, FileError> {
+        let path = path.as_ref();
+        let buffer =
+            SharedBuffer::map(path).map_err(|err| FileError::file_open(path.to_owned(), err))?;
+
+        Ok(Self::shared(buffer, Some(path.to_owned())))
+    }
+
+    fn shared(buffer: SharedBuffer, path: Option<PathBuf>) -> Self {
+        let mut reader = Self::new(Cursor::new(buffer.clone()), path);
+        reader.scanner.set_shared(buffer);
+        reader
+    }
 }
 
 impl<'a> CharReader<Cursor<&'a str>> {
@@ -38,39 +190,86 @@ impl<'a> CharReader<Cursor<&'a str>> {
 
 impl<R: BufRead> CharReader<R> {
     pub fn new(input: R, path: Option<PathBuf>) -> Self {
-        Self { input, path }
+        Self {
+            input,
+            scanner: Scanner::new(path),
+        }
+    }
+
+    #[must_use]
+    pub fn with_source(mut self, source: SourceId) -> Self {
+        self.scanner.set_source(source);
+        self
+    }
+
+    #[must_use]
+    pub fn lossless(mut self) -> Self {
+        self.scanner.set_lossless();
+        self
+    }
+
+    #[must_use]
+    pub fn encoding(mut self, encoding: Encoding) -> Self {
+        self.scanner.set_encoding(encoding);
+        self
+    }
+
+    #[must_use]
+    pub fn lossy(mut self) -> Self {
+        self.scanner.set_lossy();
+        self
+    }
+
+    #[must_use]
+    pub fn columns(mut self, columns: Columns) -> Self {
+        self.scanner.set_columns(columns);
+        self
     }
 
     pub fn read<Callback: FnMut(Span<char>) -> Continuation>(
         mut self,
         mut callback: Callback,
     ) -> Result<(), FileError> {
-        let mut buffer = String::new();
-        let mut location = Location::default();
+        while let Some(char) = self.next_char()? {
+            while let Continuation::Peek = callback(char) {}
+        }
 
-        loop {
-            buffer.clear();
+        Ok(())
+    }
 
-            match self.input.read_line(&mut buffer) {
-                Ok(0) => return Ok(()),
-                Err(err) => return Err(FileError::file_read(self.path.unwrap_or_default(), err)),
-                _ => (),
-            }
+    pub fn endings(&self) -> &LineEndings {
+        self.scanner.endings()
+    }
 
-            let mut handle_char = |char| {
-                let start = location;
-                location.advance();
-                while let Continuation::Peek = callback(Span::new(start, location, char)) {}
-            };
+    pub fn next_char(&mut self) -> Result<Option<Span<char>>, FileError> {
+        let char = self.next_event()?;
+        Ok(char.map(|span| span.map(|char| char.char)))
+    }
 
-            for char in buffer.trim_end_matches(|c| c == '\n' || c == '\r').chars() {
-                handle_char(char);
-            }
+    pub fn next_event(&mut self) -> Result<Option<Span<Char>>, FileError> {
+        self.fill()?;
+        self.scanner.next_char()
+    }
 
-            handle_char('\n');
+    pub fn next_line(&mut self) -> Result<Option<Line<'_>>, FileError> {
+        self.fill()?;
+        self.scanner.next_line()
+    }
+
+    fn fill(&mut self) -> Result<(), FileError> {
+        while self.scanner.wants_input() {
+            let input = match self.input.fill_buf() {
+                Ok(input) => input,
+                Err(err) if err.kind() == ErrorKind::Interrupted => continue,
+                Err(err) => return Err(self.scanner.read_error(err)),
+            };
 
-            location.next_line();
+            let read = input.len();
+            self.scanner.feed(input);
+            self.input.consume(read);
         }
+
+        Ok(())
     }
 
     pub fn collect(self) -> Result<Vec<Span<char>>, FileError> {
@@ -87,18 +286,23 @@ impl<R: BufRead> CharReader<R> {
 
 #[cfg(test)]
 mod test {
+    use std::sync::Arc;
+
     use super::*;
+    use crate::error::context::ErrorContext;
 
     fn span<D>(
         data: D,
         from_line: usize,
         from_col: usize,
+        from_offset: usize,
         to_line: usize,
         to_col: usize,
+        to_offset: usize,
     ) -> Span<D> {
         Span::new(
-            Location::new(from_line, from_col),
-            Location::new(to_line, to_col),
+            Location::new(from_line, from_col, from_offset),
+            Location::new(to_line, to_col, to_offset),
             data,
         )
     }
@@ -124,12 +328,13 @@ mod test {
         let input = format!("{input1}{input2}");
 
         let mut expected = expected1.to_owned();
-        let new_line = input1.ends_with(|c| c == '\n' || c == '\r');
+        let new_line = input1.ends_with(['\n', '\r']);
 
         if !new_line {
             expected.pop();
         }
 
+        let offset = input1.len();
         let (line, col) = if let Some(last) = expected.last() {
             if new_line {
                 (last.end.line + 1, 0)
@@ -146,16 +351,20 @@ mod test {
                     item.data,
                     item.start.line + line,
                     item.start.col + col,
+                    item.start.offset + offset,
                     item.end.line + line,
                     item.end.col + col,
+                    item.end.offset + offset,
                 ));
             } else {
                 expected.push(span(
                     item.data,
                     item.start.line + line,
                     item.start.col,
+                    item.start.offset + offset,
                     item.end.line + line,
                     item.end.col,
+                    item.end.offset + offset,
                 ));
             }
         }
@@ -215,58 +424,357 @@ mod test {
 
     test_cases! {
         empty_input("", &[])
-        single_space(" ", &[span(' ', 0, 0, 0, 1), span('\n', 0, 1, 0, 2)])
-        single_tab("\t", &[span('\t', 0, 0, 0, 1), span('\n', 0, 1, 0, 2)])
-        single_newline("\n", &[span('\n', 0, 0, 0, 1)])
-        single_char("a", &[span('a', 0, 0, 0, 1), span('\n', 0, 1, 0, 2)])
-        single_emojji("🐉", &[span('🐉', 0, 0, 0, 1), span('\n', 0, 1, 0, 2)])
+        single_space(" ", &[span(' ', 0, 0, 0, 0, 1, 1), span('\n', 0, 1, 1, 0, 2, 1)])
+        single_tab("\t", &[span('\t', 0, 0, 0, 0, 1, 1), span('\n', 0, 1, 1, 0, 2, 1)])
+        single_newline("\n", &[span('\n', 0, 0, 0, 0, 1, 1)])
+        single_char("a", &[span('a', 0, 0, 0, 0, 1, 1), span('\n', 0, 1, 1, 0, 2, 1)])
+        single_emojji("🐉", &[span('🐉', 0, 0, 0, 0, 1, 4), span('\n', 0, 1, 4, 0, 2, 4)])
         char_sequence(
             "a1b2c3",
             &[
-                span('a', 0, 0, 0, 1),
-                span('1', 0, 1, 0, 2),
-                span('b', 0, 2, 0, 3),
-                span('2', 0, 3, 0, 4),
-                span('c', 0, 4, 0, 5),
-                span('3', 0, 5, 0, 6),
-                span('\n', 0, 6, 0, 7)])
+                span('a', 0, 0, 0, 0, 1, 1),
+                span('1', 0, 1, 1, 0, 2, 2),
+                span('b', 0, 2, 2, 0, 3, 3),
+                span('2', 0, 3, 3, 0, 4, 4),
+                span('c', 0, 4, 4, 0, 5, 5),
+                span('3', 0, 5, 5, 0, 6, 6),
+                span('\n', 0, 6, 6, 0, 7, 6)])
         char_spaces(
             "a b c ",
             &[
-                span('a', 0, 0, 0, 1),
-                span(' ', 0, 1, 0, 2),
-                span('b', 0, 2, 0, 3),
-                span(' ', 0, 3, 0, 4),
-                span('c', 0, 4, 0, 5),
-                span(' ', 0, 5, 0, 6),
-                span('\n', 0, 6, 0, 7)])
+                span('a', 0, 0, 0, 0, 1, 1),
+                span(' ', 0, 1, 1, 0, 2, 2),
+                span('b', 0, 2, 2, 0, 3, 3),
+                span(' ', 0, 3, 3, 0, 4, 4),
+                span('c', 0, 4, 4, 0, 5, 5),
+                span(' ', 0, 5, 5, 0, 6, 6),
+                span('\n', 0, 6, 6, 0, 7, 6)])
         char_tabs(
             "a\tb\tc\t",
             &[
-                span('a', 0, 0, 0, 1),
-                span('\t', 0, 1, 0, 2),
-                span('b', 0, 2, 0, 3),
-                span('\t', 0, 3, 0, 4),
-                span('c', 0, 4, 0, 5),
-                span('\t', 0, 5, 0, 6),
-                span('\n', 0, 6, 0, 7)])
+                span('a', 0, 0, 0, 0, 1, 1),
+                span('\t', 0, 1, 1, 0, 2, 2),
+                span('b', 0, 2, 2, 0, 3, 3),
+                span('\t', 0, 3, 3, 0, 4, 4),
+                span('c', 0, 4, 4, 0, 5, 5),
+                span('\t', 0, 5, 5, 0, 6, 6),
+                span('\n', 0, 6, 6, 0, 7, 6)])
         char_lines(
             "a\nb\nc",
             &[
-                span('a', 0, 0, 0, 1),
-                span('\n', 0, 1, 0, 2),
-                span('b', 1, 0, 1, 1),
-                span('\n', 1, 1, 1, 2),
-                span('c', 2, 0, 2, 1),
-                span('\n', 2, 1, 2, 2)])
+                span('a', 0, 0, 0, 0, 1, 1),
+                span('\n', 0, 1, 1, 0, 2, 2),
+                span('b', 1, 0, 2, 1, 1, 3),
+                span('\n', 1, 1, 3, 1, 2, 4),
+                span('c', 2, 0, 4, 2, 1, 5),
+                span('\n', 2, 1, 5, 2, 2, 5)])
         emoji_lines(
             "🐉\n😛\n🍎",
             &[
-                span('🐉', 0, 0, 0, 1),
-                span('\n', 0, 1, 0, 2),
-                span('😛', 1, 0, 1, 1),
-                span('\n', 1, 1, 1, 2),
-                span('🍎', 2, 0, 2, 1),
-                span('\n', 2, 1, 2, 2)])
+                span('🐉', 0, 0, 0, 0, 1, 4),
+                span('\n', 0, 1, 4, 0, 2, 5),
+                span('😛', 1, 0, 5, 1, 1, 9),
+                span('\n', 1, 1, 9, 1, 2, 10),
+                span('🍎', 2, 0, 10, 2, 1, 14),
+                span('\n', 2, 1, 14, 2, 2, 14)])
+        crlf_lines(
+            "a\r\nb\r\n",
+            &[
+                span('a', 0, 0, 0, 0, 1, 1),
+                span('\n', 0, 1, 1, 0, 2, 3),
+                span('b', 1, 0, 3, 1, 1, 4),
+                span('\n', 1, 1, 4, 1, 2, 6)])
+        cr_lines(
+            "a\rb\rc",
+            &[
+                span('a', 0, 0, 0, 0, 1, 1),
+                span('\n', 0, 1, 1, 0, 2, 2),
+                span('b', 1, 0, 2, 1, 1, 3),
+                span('\n', 1, 1, 3, 1, 2, 4),
+                span('c', 2, 0, 4, 2, 1, 5),
+                span('\n', 2, 1, 5, 2, 2, 5)])
+    }
+
+    #[test]
+    fn read_with_source() {
+        let mut sources = SourceMap::new();
+        sources.add("first.txt");
+        let source = sources.add("second.txt");
+
+        let result = CharReader::with_str("a\nb")
+            .with_source(source)
+            .collect()
+            .unwrap();
+
+        assert_eq!(result.len(), 4);
+        assert!(result.iter().all(|span| span.source() == source));
+    }
+
+    #[test]
+    fn read_lossless() {
+        let input = "a\r\n\rb\n c";
+        let result = CharReader::with_str(input).lossless().collect().unwrap();
+
+        assert_eq!(
+            result.iter().map(|span| span.data).collect::<String>(),
+            input
+        );
+        assert!(result
+            .iter()
+            .all(|span| span.end.offset - span.start.offset == span.data.len_utf8()));
+        assert_eq!(result.last(), Some(&span('c', 3, 1, 7, 3, 2, 8)));
+    }
+
+    #[test]
+    fn read_line_endings() {
+        let mut reader = CharReader::with_str("a\r\nb\rc\r\nd");
+        let mut endings = Vec::new();
+
+        while let Some(line) = reader.next_line().unwrap() {
+            endings.push((line.text.to_owned(), line.ending, line.newline.range()));
+        }
+
+        assert_eq!(
+            endings,
+            [
+                ("a".to_owned(), LineEnding::CrLf, 1..3),
+                ("b".to_owned(), LineEnding::Cr, 4..5),
+                ("c".to_owned(), LineEnding::CrLf, 6..8),
+                ("d".to_owned(), LineEnding::Eof, 9..9),
+            ]
+        );
+
+        assert!(reader.endings().is_mixed());
+        assert!(reader.endings().missing_final_newline);
+        assert_eq!(reader.endings().most_common(), Some(LineEnding::CrLf));
+    }
+
+    #[test]
+    fn read_events() {
+        let events = |mut reader: CharReader<_>| {
+            let mut events = Vec::new();
+
+            while let Some(span) = reader.next_event().unwrap() {
+                events.push((span.data.char, span.data.ending, span.data.is_synthetic()));
+            }
+
+            events
+        };
+
+        assert_eq!(
+            events(CharReader::with_str("a\r\nb\rc")),
+            [
+                ('a', None, false),
+                ('\n', Some(LineEnding::CrLf), false),
+                ('b', None, false),
+                ('\n', Some(LineEnding::Cr), false),
+                ('c', None, false),
+                ('\n', Some(LineEnding::Eof), true),
+            ]
+        );
+
+        assert_eq!(
+            events(CharReader::with_str("a\r\nb").lossless()),
+            [
+                ('a', None, false),
+                ('\r', Some(LineEnding::CrLf), false),
+                ('\n', Some(LineEnding::CrLf), false),
+                ('b', None, false),
+            ]
+        );
+    }
+
+    #[test]
+    fn read_columns() {
+        let input = "\ta漢e\u{301}👨\u{200d}👩b";
+        let cols = |columns| {
+            CharReader::with_str(input)
+                .columns(columns)
+                .collect()
+                .unwrap()
+                .iter()
+                .map(|span| span.end.col)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(cols(Columns::Chars), [1, 2, 3, 4, 5, 6, 7, 8, 9, 10]);
+        assert_eq!(cols(Columns::Bytes), [1, 2, 5, 6, 8, 12, 15, 19, 20, 21]);
+        assert_eq!(cols(Columns::Utf16), [1, 2, 3, 4, 5, 7, 8, 10, 11, 12]);
+        assert_eq!(
+            cols(Columns::Display { tab_stop: 4 }),
+            [4, 5, 7, 8, 8, 10, 10, 10, 11, 12]
+        );
+
+        let mut reader = CharReader::with_str(input).columns(Columns::Display { tab_stop: 4 });
+        let line = reader.next_line().unwrap().unwrap();
+
+        assert_eq!(
+            line.chars().map(|span| span.end.col).collect::<Vec<_>>(),
+            [4, 5, 7, 8, 8, 10, 10, 10, 11]
+        );
+        assert_eq!(line.newline.end.col, 12);
+    }
+
+    fn decode(
+        input: &[u8],
+        reader: impl FnOnce(CharReader<BufReader<&[u8]>>) -> CharReader<BufReader<&[u8]>>,
+    ) -> Result<String, FileError> {
+        let reader = reader(CharReader::new(BufReader::with_capacity(1, input), None));
+        let chars = reader.collect()?;
+
+        Ok(chars.into_iter().map(|span| span.data).collect())
+    }
+
+    #[test]
+    fn read_encodings() {
+        let utf16 = |bom: &[u8], unit: fn(u16) -> [u8; 2]| {
+            let mut bytes = bom.to_owned();
+            bytes.extend("h€\n😀".encode_utf16().flat_map(unit));
+            bytes
+        };
+
+        let expected = Some("h€\n😀\n".to_owned());
+
+        assert_eq!(
+            decode(b"\xEF\xBB\xBFh\xE2\x82\xAC\n\xF0\x9F\x98\x80", |r| r).ok(),
+            expected.clone()
+        );
+        assert_eq!(
+            decode(&utf16(b"\xFF\xFE", u16::to_le_bytes), |r| r).ok(),
+            expected.clone()
+        );
+        assert_eq!(
+            decode(&utf16(b"\xFE\xFF", u16::to_be_bytes), |r| r).ok(),
+            expected.clone()
+        );
+        assert_eq!(
+            decode(&utf16(b"", u16::to_be_bytes), |r| r
+                .encoding(Encoding::Utf16Be))
+            .ok(),
+            expected
+        );
+        assert_eq!(
+            decode(b"caf\xE9", |r| r.encoding(Encoding::Latin1)).ok(),
+            Some("café\n".to_owned())
+        );
+        assert_eq!(
+            decode(b"a\rb\r\rc", |r| r).ok(),
+            Some("a\nb\n\nc\n".to_owned())
+        );
+    }
+
+    #[test]
+    fn read_invalid_encoding() {
+        let error = decode(b"ab\n c\xFFd", |r| r).err();
+
+        let Some(FileError::InvalidEncoding {
+            span,
+            encoding,
+            bytes,
+            ..
+        }) = error
+        else {
+            panic!("expected an invalid encoding error, got {error:?}");
+        };
+
+        assert_eq!(span.start, Location::new(1, 2, 5));
+        assert_eq!(span.end, Location::new(1, 3, 6));
+        assert_eq!(encoding, Encoding::Utf8);
+        assert_eq!(bytes, b"\xFF");
+
+        assert_eq!(
+            decode(b"ab\xF0\x9F\n\xFF", |r| r.lossy()).ok(),
+            Some("ab\u{FFFD}\n\u{FFFD}\n".to_owned())
+        );
+        assert!(decode(b"\xFF\xFEa\x00\x00\xD8", |r| r).is_err());
+    }
+
+    #[test]
+    fn read_named_reader() {
+        struct Broken;
+
+        impl Read for Broken {
+            fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+                Err(io::Error::other("archive is corrupt"))
+            }
+        }
+
+        let error = CharReader::with_reader("config.toml (from archive)", Broken)
+            .collect()
+            .err();
+
+        assert!(matches!(
+            error,
+            Some(FileError::FileRead { file, .. }) if file == Path::new("config.toml (from archive)")
+        ));
+
+        let chars = CharReader::with_reader("<memory>", "ab".as_bytes())
+            .collect()
+            .unwrap();
+
+        assert_eq!(chars.len(), 3);
+    }
+
+    #[test]
+    fn read_shared_buffer() {
+        let text: Arc<str> = Arc::from("owned\ntext");
+        let buffer = SharedBuffer::from(text);
+
+        let reader = CharReader::with_buffer(buffer.clone());
+        let chars = reader.collect().unwrap();
+        assert_eq!(
+            chars.iter().map(|span| span.data).collect::<String>(),
+            "owned\ntext\n"
+        );
+
+        let mut reader = CharReader::with_buffer(buffer.clone());
+        let line = reader.next_line().unwrap().unwrap();
+        assert_eq!(line.text, "owned");
+        assert_eq!(line.text.as_ptr(), buffer.as_bytes().as_ptr());
+
+        let chars = CharReader::with_buffer("\u{FEFF}é\n").collect().unwrap();
+        assert_eq!(chars[0], span('é', 0, 0, 0, 0, 1, 2));
+
+        let chars = CharReader::with_buffer("é")
+            .encoding(Encoding::Latin1)
+            .collect()
+            .unwrap();
+        assert_eq!(
+            chars.iter().map(|span| span.data).collect::<String>(),
+            "Ã©\n"
+        );
+
+        assert!(CharReader::with_buffer(b"a\xFF".to_vec())
+            .collect()
+            .is_err());
+
+        let context = ErrorContext::from_buffer(buffer, None);
+        assert_eq!(
+            context
+                .lock()
+                .unwrap()
+                .line(SourceId::default(), 1)
+                .unwrap(),
+            "text"
+        );
+    }
+
+    #[cfg(feature = "mmap")]
+    #[test]
+    fn read_mmap() {
+        let path = std::env::temp_dir().join(format!("ruinous-mmap-{}.txt", std::process::id()));
+        std::fs::write(&path, "mapped\r\nfile").unwrap();
+
+        let chars = CharReader::with_mmap(&path).unwrap().collect();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(
+            chars
+                .unwrap()
+                .iter()
+                .map(|span| span.data)
+                .collect::<String>(),
+            "mapped\nfile\n"
+        );
     }
 }
@@ -0,0 +1,330 @@
+use std::path::PathBuf;
+
+use crate::{
+    encoding::{Decoder, Encoding},
+    source::{SharedBuffer, SourceId},
+    span::{Columns, Location, Span},
+};
+
+use super::{error::FileError, Char, Line, LineEnding, LineEndings};
+
+/// The input-independent half of a reader, which decodes the bytes it is fed and
+/// splits them into lines and characters, or reads them straight out of a shared buffer
+/// that is already text
+pub(crate) struct Scanner {
+    path: Option<PathBuf>,
+    shared: Option<SharedBuffer>,
+    buffer: String,
+    decoder: Decoder,
+    decoded: String,
+    consumed: usize,
+    scanned: usize,
+    eof: bool,
+    invalid: Option<Vec<u8>>,
+    position: usize,
+    line: Option<LineBreak>,
+    location: Location,
+    lossless: bool,
+    columns: Columns,
+    endings: LineEndings,
+}
+
+#[derive(Clone, Copy)]
+struct LineBreak {
+    start: usize,
+    end: usize,
+    ending: LineEnding,
+}
+
+impl Scanner {
+    pub fn new(path: Option<PathBuf>) -> Self {
+        Self {
+            path,
+            shared: None,
+            buffer: String::new(),
+            decoder: Decoder::new(),
+            decoded: String::new(),
+            consumed: 0,
+            scanned: 0,
+            eof: false,
+            invalid: None,
+            position: 0,
+            line: None,
+            location: Location::default(),
+            lossless: false,
+            columns: Columns::default(),
+            endings: LineEndings::default(),
+        }
+    }
+
+    pub fn set_source(&mut self, source: SourceId) {
+        self.location = self.location.in_source(source);
+    }
+
+    pub fn set_lossless(&mut self) {
+        self.lossless = true;
+    }
+
+    /// Read lines directly from `buffer` instead of decoding its bytes as they are fed in,
+    /// as long as it is text and isn't later given another encoding
+    pub fn set_shared(&mut self, buffer: SharedBuffer) {
+        if let Some(text) = buffer.as_str() {
+            self.position = text
+                .strip_prefix('\u{FEFF}')
+                .map_or(0, |_| '\u{FEFF}'.len_utf8());
+            self.shared = Some(buffer);
+        }
+    }
+
+    pub fn set_encoding(&mut self, encoding: Encoding) {
+        if encoding != Encoding::Utf8 && self.shared.take().is_some() {
+            self.position = 0;
+        }
+
+        self.decoder.set_encoding(encoding);
+    }
+
+    pub fn set_lossy(&mut self) {
+        self.decoder.set_lossy(true);
+    }
+
+    pub fn set_columns(&mut self, columns: Columns) {
+        self.columns = columns;
+    }
+
+    pub fn endings(&self) -> &LineEndings {
+        &self.endings
+    }
+
+    /// Whether more input must be fed in before the next line can be scanned, which only
+    /// searches the input decoded since the last call for a newline
+    pub fn wants_input(&mut self) -> bool {
+        if self.shared.is_some()
+            || self.line.is_some()
+            || self.position < self.buffer.len()
+            || self.eof
+        {
+            return false;
+        }
+
+        self.scanned = self.scanned.max(self.consumed);
+
+        match self.decoded[self.scanned..].find('\n') {
+            Some(index) => {
+                self.scanned += index;
+                false
+            }
+            None => {
+                self.scanned = self.decoded.len();
+                true
+            }
+        }
+    }
+
+    /// Feed in the next chunk of input, where an empty chunk marks the end of the input
+    pub fn feed(&mut self, input: &[u8]) {
+        self.eof = input.is_empty();
+        self.decoded.drain(..self.consumed);
+        self.scanned = self.scanned.saturating_sub(self.consumed);
+        self.consumed = 0;
+
+        if let Err(bytes) = self.decoder.decode(input, self.eof, &mut self.decoded) {
+            self.invalid = Some(bytes);
+            self.eof = true;
+        }
+    }
+
+    pub fn read_error(&self, error: std::io::Error) -> FileError {
+        FileError::file_read(self.path.clone().unwrap_or_default(), error)
+    }
+
+    pub fn next_char(&mut self) -> Result<Option<Span<Char>>, FileError> {
+        let line = match self.line {
+            Some(line) => line,
+            None => match self.read_line()? {
+                Some(line) => line,
+                None => return Ok(None),
+            },
+        };
+
+        let start = self.location;
+        let end = if self.lossless { line.end } else { line.start };
+
+        if let Some(char) = self.text()[self.position..end].chars().next() {
+            let width = self
+                .columns
+                .width(&self.text()[..end], self.position, start.col);
+            let ending = (self.position >= line.start).then_some(line.ending);
+
+            self.position += char.len_utf8();
+            self.location.advance_by(char.len_utf8(), width);
+
+            let span = Span::new(start, self.location, Char { char, ending });
+
+            if self.lossless && self.position == line.end && !self.truncated(line) {
+                self.finish_line();
+            }
+
+            return Ok(Some(span));
+        }
+
+        if self.truncated(line) {
+            return Err(self.invalid_encoding());
+        }
+
+        self.location.advance(line.end - self.position);
+        let span = Span::new(
+            start,
+            self.location,
+            Char {
+                char: '\n',
+                ending: Some(line.ending),
+            },
+        );
+        self.finish_line();
+
+        Ok(Some(span))
+    }
+
+    pub fn next_line(&mut self) -> Result<Option<Line<'_>>, FileError> {
+        let line = match self.line {
+            Some(line) => line,
+            None => match self.read_line()? {
+                Some(line) => line,
+                None => return Ok(None),
+            },
+        };
+
+        if self.truncated(line) && self.position >= line.start {
+            return Err(self.invalid_encoding());
+        }
+
+        let position = self.position.min(line.start);
+        let start = self.location;
+        let text = &self.text()[..line.start];
+        let mut location = self.location;
+
+        for (index, char) in text[position..].char_indices() {
+            let width = self.columns.width(text, position + index, location.col);
+            location.advance_by(char.len_utf8(), width);
+        }
+
+        self.location = location;
+
+        let newline_start = self.location;
+        self.location
+            .advance(line.end - line.start.max(self.position));
+        let newline = Span::new(newline_start, self.location, '\n');
+
+        if self.truncated(line) {
+            self.position = line.start;
+        } else {
+            self.finish_line();
+        }
+
+        Ok(Some(Line {
+            text: &self.text()[position..line.start],
+            start,
+            newline,
+            ending: line.ending,
+            columns: self.columns,
+        }))
+    }
+
+    fn text(&self) -> &str {
+        self.shared
+            .as_ref()
+            .and_then(SharedBuffer::as_str)
+            .unwrap_or(&self.buffer)
+    }
+
+    fn finish_line(&mut self) {
+        if let Some(line) = self.line.take() {
+            self.position = line.end;
+            self.location.next_line();
+        }
+    }
+
+    /// Whether the current line was cut short by an invalid sequence
+    fn truncated(&self, line: LineBreak) -> bool {
+        line.ending.is_synthetic() && self.invalid.is_some()
+    }
+
+    fn invalid_encoding(&mut self) -> FileError {
+        let bytes = self.invalid.take().unwrap_or_default();
+
+        let mut end = self.location;
+        end.advance(bytes.len());
+
+        FileError::invalid_encoding(
+            self.path.clone().unwrap_or_default(),
+            Span::new(self.location, end, ()),
+            self.decoder.encoding(),
+            bytes,
+        )
+    }
+
+    fn fill_buffer(&mut self) -> bool {
+        let rest = &self.decoded[self.consumed..];
+        let end = rest.find('\n').map_or(rest.len(), |index| index + 1);
+
+        self.buffer.push_str(&rest[..end]);
+        self.consumed += end;
+
+        !self.buffer.is_empty()
+    }
+
+    fn read_line(&mut self) -> Result<Option<LineBreak>, FileError> {
+        if self.position >= self.text().len() {
+            if self.shared.is_some() {
+                return Ok(None);
+            }
+
+            self.buffer.clear();
+            self.position = 0;
+
+            if !self.fill_buffer() {
+                return match self.invalid {
+                    Some(_) => Err(self.invalid_encoding()),
+                    None => Ok(None),
+                };
+            }
+        }
+
+        let text = self.text();
+        let rest = &text[self.position..];
+        let line = match rest.find(['\n', '\r']) {
+            Some(index) => {
+                let start = self.position + index;
+
+                match &rest[index..] {
+                    text if text.starts_with("\r\n") => LineBreak {
+                        start,
+                        end: start + 2,
+                        ending: LineEnding::CrLf,
+                    },
+                    text if text.starts_with('\r') => LineBreak {
+                        start,
+                        end: start + 1,
+                        ending: LineEnding::Cr,
+                    },
+                    _ => LineBreak {
+                        start,
+                        end: start + 1,
+                        ending: LineEnding::Lf,
+                    },
+                }
+            }
+            None => LineBreak {
+                start: text.len(),
+                end: text.len(),
+                ending: LineEnding::Eof,
+            },
+        };
+
+        self.endings.record(line.ending);
+        self.line = Some(line);
+
+        Ok(Some(line))
+    }
+}
@@ -0,0 +1,274 @@
+use std::{
+    borrow::Cow,
+    collections::{BTreeSet, HashMap},
+};
+
+use super::{
+    nfa::{Nfa, Node},
+    pattern::next_char,
+};
+
+const DEAD: u32 = 0;
+
+/// A minimised DFA over character classes, which can either be built from an [`Nfa`]
+/// or borrow tables that were generated ahead of time.
+#[derive(Debug, Clone)]
+pub struct Dfa {
+    ascii: [u16; 128],
+    ranges: Cow<'static, [(char, u16)]>,
+    classes: usize,
+    transitions: Cow<'static, [u32]>,
+    accepts: Cow<'static, [Option<usize>]>,
+    finals: Cow<'static, [bool]>,
+    start: u32,
+}
+
+/// The tables making up a [`Dfa`], as generated ahead of time.
+#[derive(Debug, Clone, Copy)]
+pub struct Tables<'a> {
+    pub ascii: [u16; 128],
+    pub ranges: &'a [(char, u16)],
+    pub classes: usize,
+    pub transitions: &'a [u32],
+    pub accepts: &'a [Option<usize>],
+    pub start: u32,
+}
+
+impl Dfa {
+    pub fn new(nfa: &Nfa) -> Self {
+        let (ranges, classes) = Self::partition(nfa);
+
+        let mut ascii = [0; 128];
+
+        for (char, class) in ascii.iter_mut().enumerate() {
+            *class = Self::lookup(&ranges, char::from(char as u8));
+        }
+
+        let representatives = (0..classes)
+            .map(|class| {
+                ranges
+                    .iter()
+                    .find(|&&(_, found)| usize::from(found) == class)
+                    .map(|&(char, _)| char)
+            })
+            .collect::<Vec<_>>();
+
+        let (transitions, accepts, start) = Self::subsets(nfa, &representatives);
+        let (transitions, accepts, start) = Self::minimise(&transitions, &accepts, start, classes);
+
+        let finals = Self::finals(&transitions, classes);
+
+        Self {
+            ascii,
+            ranges: ranges.into(),
+            classes,
+            transitions: transitions.into(),
+            accepts: accepts.into(),
+            finals: finals.into(),
+            start,
+        }
+    }
+
+    pub fn from_tables(tables: Tables<'static>) -> Self {
+        let finals = Self::finals(tables.transitions, tables.classes);
+
+        Self {
+            ascii: tables.ascii,
+            ranges: tables.ranges.into(),
+            classes: tables.classes,
+            transitions: tables.transitions.into(),
+            accepts: tables.accepts.into(),
+            finals: finals.into(),
+            start: tables.start,
+        }
+    }
+
+    pub fn tables(&self) -> Tables<'_> {
+        Tables {
+            ascii: self.ascii,
+            ranges: &self.ranges,
+            classes: self.classes,
+            transitions: &self.transitions,
+            accepts: &self.accepts,
+            start: self.start,
+        }
+    }
+
+    pub fn states(&self) -> usize {
+        self.accepts.len()
+    }
+
+    pub fn start(&self) -> u32 {
+        self.start
+    }
+
+    pub fn step(&self, state: u32, char: char) -> Option<u32> {
+        let next = self.transitions[state as usize * self.classes + self.class(char)];
+        (next != DEAD).then_some(next)
+    }
+
+    pub fn accepts(&self, state: u32) -> Option<usize> {
+        self.accepts[state as usize]
+    }
+
+    pub fn is_final(&self, state: u32) -> bool {
+        self.finals[state as usize]
+    }
+
+    fn class(&self, char: char) -> usize {
+        match self.ascii.get(char as usize) {
+            Some(&class) => usize::from(class),
+            None => usize::from(Self::lookup(&self.ranges, char)),
+        }
+    }
+
+    fn finals(transitions: &[u32], classes: usize) -> Vec<bool> {
+        transitions
+            .chunks(classes)
+            .map(|row| row.iter().all(|&next| next == DEAD))
+            .collect()
+    }
+
+    fn lookup(ranges: &[(char, u16)], char: char) -> u16 {
+        let index = ranges.partition_point(|&(start, _)| start <= char);
+        ranges[index - 1].1
+    }
+
+    fn partition(nfa: &Nfa) -> (Vec<(char, u16)>, usize) {
+        let sets = nfa
+            .nodes
+            .iter()
+            .filter_map(|node| match node {
+                Node::Set(set, _) => Some(set),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+
+        let mut boundaries = BTreeSet::from(['\0']);
+
+        for set in &sets {
+            for &(from, to) in set.ranges() {
+                boundaries.insert(from);
+                boundaries.extend(next_char(to));
+            }
+        }
+
+        let mut classes = HashMap::from([(Vec::new(), 0)]);
+        let mut ranges = Vec::with_capacity(boundaries.len());
+
+        for start in boundaries {
+            let signature = sets
+                .iter()
+                .enumerate()
+                .filter(|(_, set)| set.contains(start))
+                .map(|(index, _)| index)
+                .collect::<Vec<_>>();
+
+            let next = classes.len();
+            let class = *classes.entry(signature).or_insert(next);
+
+            ranges.push((
+                start,
+                u16::try_from(class).expect("too many character classes"),
+            ));
+        }
+
+        (ranges, classes.len())
+    }
+
+    fn subsets(nfa: &Nfa, representatives: &[Option<char>]) -> (Vec<u32>, Vec<Option<usize>>, u32) {
+        let classes = representatives.len();
+
+        let mut ids = HashMap::from([(Vec::new(), DEAD)]);
+        let mut states = vec![Vec::new()];
+        let mut transitions = Vec::new();
+        let mut current = 0;
+
+        let start = *ids.entry(nfa.start()).or_insert_with_key(|start| {
+            states.push(start.clone());
+            1
+        });
+
+        while current < states.len() {
+            for representative in representatives {
+                let next = match representative {
+                    Some(char) => nfa.step_set(&states[current], *char),
+                    None => Vec::new(),
+                };
+
+                let id = *ids.entry(next.clone()).or_insert_with(|| {
+                    states.push(next);
+                    u32::try_from(states.len() - 1).expect("too many lexer states")
+                });
+
+                transitions.push(id);
+            }
+
+            current += 1;
+        }
+
+        debug_assert_eq!(transitions.len(), states.len() * classes);
+
+        let accepts = states.iter().map(|state| nfa.best_match(state)).collect();
+        (transitions, accepts, start)
+    }
+
+    fn minimise(
+        transitions: &[u32],
+        accepts: &[Option<usize>],
+        start: u32,
+        classes: usize,
+    ) -> (Vec<u32>, Vec<Option<usize>>, u32) {
+        let mut blocks = Self::renumber(
+            accepts
+                .iter()
+                .enumerate()
+                .map(|(state, accept)| (usize::from(state != DEAD as usize), *accept)),
+        );
+
+        loop {
+            let refined = Self::renumber((0..accepts.len()).map(|state| {
+                let row = &transitions[state * classes..(state + 1) * classes];
+                let targets = row
+                    .iter()
+                    .map(|&target| blocks[target as usize])
+                    .collect::<Vec<_>>();
+
+                (blocks[state], targets)
+            }));
+
+            let done = refined.iter().max() == blocks.iter().max();
+            blocks = refined;
+
+            if done {
+                break;
+            }
+        }
+
+        let count = blocks.iter().max().map_or(0, |max| max + 1) as usize;
+        let mut minimal = vec![DEAD; count * classes];
+        let mut minimal_accepts = vec![None; count];
+
+        for (state, &block) in blocks.iter().enumerate() {
+            let block = block as usize;
+            minimal_accepts[block] = accepts[state];
+
+            for class in 0..classes {
+                minimal[block * classes + class] =
+                    blocks[transitions[state * classes + class] as usize];
+            }
+        }
+
+        (minimal, minimal_accepts, blocks[start as usize])
+    }
+
+    fn renumber<K: Eq + std::hash::Hash, I: Iterator<Item = K>>(keys: I) -> Vec<u32> {
+        let mut ids = HashMap::new();
+
+        keys.map(|key| {
+            let next = u32::try_from(ids.len()).expect("too many lexer states");
+            *ids.entry(key).or_insert(next)
+        })
+        .collect()
+    }
+}
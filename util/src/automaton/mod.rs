@@ -0,0 +1,3 @@
+pub mod dfa;
+pub mod nfa;
+pub mod pattern;
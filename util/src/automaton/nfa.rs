@@ -0,0 +1,118 @@
+use super::pattern::{CharSet, Pattern};
+
+#[derive(Debug, Clone)]
+pub enum Node {
+    Set(CharSet, usize),
+    Split(Vec<usize>),
+    Match(usize),
+}
+
+#[derive(Default, Debug, Clone)]
+pub struct Nfa {
+    pub nodes: Vec<Node>,
+    pub priorities: Vec<i32>,
+    start: Vec<usize>,
+}
+
+impl Nfa {
+    pub fn new<'a, I: IntoIterator<Item = (&'a Pattern, i32)>>(patterns: I) -> Self {
+        let mut nfa = Self::default();
+        let mut starts = Vec::new();
+
+        for (rule, (pattern, priority)) in patterns.into_iter().enumerate() {
+            let end = nfa.push(Node::Match(rule));
+            starts.push(nfa.compile(pattern, end));
+            nfa.priorities.push(priority);
+        }
+
+        nfa.start = nfa.closure(starts);
+        nfa
+    }
+
+    pub fn start(&self) -> Vec<usize> {
+        self.start.clone()
+    }
+
+    pub fn is_final(&self, states: &[usize]) -> bool {
+        states
+            .iter()
+            .all(|&state| matches!(self.nodes[state], Node::Match(_)))
+    }
+
+    pub fn step_set(&self, states: &[usize], char: char) -> Vec<usize> {
+        self.closure(states.iter().filter_map(|&state| match &self.nodes[state] {
+            Node::Set(set, next) if set.contains(char) => Some(*next),
+            _ => None,
+        }))
+    }
+
+    pub fn best_match(&self, states: &[usize]) -> Option<usize> {
+        states
+            .iter()
+            .filter_map(|&state| match self.nodes[state] {
+                Node::Match(rule) => Some(rule),
+                _ => None,
+            })
+            .max_by_key(|&rule| (self.priorities[rule], std::cmp::Reverse(rule)))
+    }
+
+    pub fn closure<I: IntoIterator<Item = usize>>(&self, states: I) -> Vec<usize> {
+        let mut stack = states.into_iter().collect::<Vec<_>>();
+        let mut seen = vec![false; self.nodes.len()];
+        let mut result = Vec::new();
+
+        while let Some(state) = stack.pop() {
+            if std::mem::replace(&mut seen[state], true) {
+                continue;
+            }
+
+            match &self.nodes[state] {
+                Node::Split(next) => stack.extend(next.iter().rev()),
+                _ => result.push(state),
+            }
+        }
+
+        result.sort_unstable();
+        result
+    }
+
+    fn push(&mut self, node: Node) -> usize {
+        self.nodes.push(node);
+        self.nodes.len() - 1
+    }
+
+    fn compile(&mut self, pattern: &Pattern, next: usize) -> usize {
+        match pattern {
+            Pattern::Empty => next,
+            Pattern::Set(set) => self.push(Node::Set(set.clone(), next)),
+            Pattern::Concat(items) => items
+                .iter()
+                .rev()
+                .fold(next, |next, item| self.compile(item, next)),
+            Pattern::Alternate(branches) => {
+                let starts = branches
+                    .iter()
+                    .map(|branch| self.compile(branch, next))
+                    .collect();
+
+                self.push(Node::Split(starts))
+            }
+            Pattern::Repeat { pattern, min, max } => {
+                let tail = match max {
+                    Some(max) => (*min..*max).fold(next, |next, _| {
+                        let item = self.compile(pattern, next);
+                        self.push(Node::Split(vec![item, next]))
+                    }),
+                    None => {
+                        let split = self.push(Node::Split(Vec::new()));
+                        let item = self.compile(pattern, split);
+                        self.nodes[split] = Node::Split(vec![item, next]);
+                        split
+                    }
+                };
+
+                (0..*min).fold(tail, |next, _| self.compile(pattern, next))
+            }
+        }
+    }
+}
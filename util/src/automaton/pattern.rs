@@ -0,0 +1,450 @@
+use std::{fmt::Display, iter::Peekable, str::CharIndices};
+
+#[derive(Default, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CharSet {
+    ranges: Vec<(char, char)>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Pattern {
+    Empty,
+    Set(CharSet),
+    Concat(Vec<Pattern>),
+    Alternate(Vec<Pattern>),
+    Repeat {
+        pattern: Box<Pattern>,
+        min: usize,
+        max: Option<usize>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PatternError {
+    pub pattern: String,
+    pub offset: usize,
+    pub kind: PatternErrorKind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PatternErrorKind {
+    UnexpectedEnd,
+    UnexpectedChar(char),
+    UnclosedGroup,
+    UnclosedClass,
+    InvalidRange,
+    InvalidRepetition,
+    NothingToRepeat,
+}
+
+struct Parser<'a> {
+    pattern: &'a str,
+    chars: Peekable<CharIndices<'a>>,
+}
+
+impl CharSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn single(char: char) -> Self {
+        Self::range(char, char)
+    }
+
+    pub fn range(from: char, to: char) -> Self {
+        let mut set = Self::new();
+        set.insert(from, to);
+        set
+    }
+
+    pub fn any() -> Self {
+        Self::range('\0', char::MAX)
+    }
+
+    pub fn digits() -> Self {
+        Self::range('0', '9')
+    }
+
+    pub fn word() -> Self {
+        Self::range('a', 'z')
+            .union(&Self::range('A', 'Z'))
+            .union(&Self::digits())
+            .union(&Self::single('_'))
+    }
+
+    pub fn whitespace() -> Self {
+        " \t\n\r\x0b\x0c".chars().collect()
+    }
+
+    pub fn contains(&self, char: char) -> bool {
+        self.ranges
+            .binary_search_by(|&(from, to)| {
+                if to < char {
+                    std::cmp::Ordering::Less
+                } else if from > char {
+                    std::cmp::Ordering::Greater
+                } else {
+                    std::cmp::Ordering::Equal
+                }
+            })
+            .is_ok()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+
+    pub fn ranges(&self) -> &[(char, char)] {
+        &self.ranges
+    }
+
+    pub fn insert(&mut self, from: char, to: char) {
+        let (from, to) = if from <= to { (from, to) } else { (to, from) };
+        self.ranges.push((from, to));
+        self.ranges.sort_unstable();
+
+        let mut merged: Vec<(char, char)> = Vec::with_capacity(self.ranges.len());
+
+        for &(from, to) in &self.ranges {
+            match merged.last_mut() {
+                Some(last) if u32::from(from) <= u32::from(last.1) + 1 => {
+                    last.1 = last.1.max(to);
+                }
+                _ => merged.push((from, to)),
+            }
+        }
+
+        self.ranges = merged;
+    }
+
+    #[must_use]
+    pub fn union(&self, other: &CharSet) -> Self {
+        let mut set = self.clone();
+
+        for &(from, to) in &other.ranges {
+            set.insert(from, to);
+        }
+
+        set
+    }
+
+    #[must_use]
+    pub fn negate(&self) -> Self {
+        let mut set = Self::new();
+        let mut next = Some('\0');
+
+        for &(from, to) in &self.ranges {
+            if let Some(start) = next {
+                if start < from {
+                    set.insert(start, previous_char(from));
+                }
+            }
+
+            next = next_char(to);
+        }
+
+        if let Some(start) = next {
+            set.insert(start, char::MAX);
+        }
+
+        set
+    }
+}
+
+impl FromIterator<char> for CharSet {
+    fn from_iter<T: IntoIterator<Item = char>>(iter: T) -> Self {
+        let mut set = Self::new();
+
+        for char in iter {
+            set.insert(char, char);
+        }
+
+        set
+    }
+}
+
+pub fn next_char(char: char) -> Option<char> {
+    match char {
+        '\u{d7ff}' => Some('\u{e000}'),
+        char::MAX => None,
+        _ => char::from_u32(u32::from(char) + 1),
+    }
+}
+
+pub fn previous_char(char: char) -> char {
+    match char {
+        '\u{e000}' => '\u{d7ff}',
+        _ => char::from_u32(u32::from(char) - 1).unwrap_or('\0'),
+    }
+}
+
+impl Pattern {
+    pub fn literal(text: &str) -> Self {
+        Pattern::Concat(
+            text.chars()
+                .map(CharSet::single)
+                .map(Pattern::Set)
+                .collect(),
+        )
+    }
+
+    pub fn parse(pattern: &str) -> Result<Self, PatternError> {
+        let mut parser = Parser {
+            pattern,
+            chars: pattern.char_indices().peekable(),
+        };
+
+        let result = parser.alternation()?;
+
+        match parser.chars.next() {
+            Some((offset, char)) => {
+                Err(parser.error_at(offset, PatternErrorKind::UnexpectedChar(char)))
+            }
+            None => Ok(result),
+        }
+    }
+}
+
+impl<'a> Parser<'a> {
+    fn error_at(&self, offset: usize, kind: PatternErrorKind) -> PatternError {
+        PatternError {
+            pattern: self.pattern.to_owned(),
+            offset,
+            kind,
+        }
+    }
+
+    fn error(&mut self, kind: PatternErrorKind) -> PatternError {
+        let offset = self
+            .chars
+            .peek()
+            .map_or(self.pattern.len(), |&(offset, _)| offset);
+
+        self.error_at(offset, kind)
+    }
+
+    fn next(&mut self) -> Result<(usize, char), PatternError> {
+        self.chars
+            .next()
+            .ok_or_else(|| self.error_at(self.pattern.len(), PatternErrorKind::UnexpectedEnd))
+    }
+
+    fn eat(&mut self, expected: char) -> bool {
+        self.chars.next_if(|&(_, char)| char == expected).is_some()
+    }
+
+    fn alternation(&mut self) -> Result<Pattern, PatternError> {
+        let mut branches = vec![self.concatenation()?];
+
+        while self.eat('|') {
+            branches.push(self.concatenation()?);
+        }
+
+        Ok(if branches.len() == 1 {
+            branches.remove(0)
+        } else {
+            Pattern::Alternate(branches)
+        })
+    }
+
+    fn concatenation(&mut self) -> Result<Pattern, PatternError> {
+        let mut items = Vec::new();
+
+        while let Some(&(_, char)) = self.chars.peek() {
+            if char == '|' || char == ')' {
+                break;
+            }
+
+            let atom = self.atom()?;
+            items.push(self.repetition(atom)?);
+        }
+
+        Ok(match items.len() {
+            0 => Pattern::Empty,
+            1 => items.remove(0),
+            _ => Pattern::Concat(items),
+        })
+    }
+
+    fn atom(&mut self) -> Result<Pattern, PatternError> {
+        let (offset, char) = self.next()?;
+
+        match char {
+            '(' => {
+                if self.eat('?') && !self.eat(':') {
+                    return Err(self.error(PatternErrorKind::UnexpectedChar('?')));
+                }
+
+                let inner = self.alternation()?;
+
+                if self.eat(')') {
+                    Ok(inner)
+                } else {
+                    Err(self.error_at(offset, PatternErrorKind::UnclosedGroup))
+                }
+            }
+            '[' => self.class(offset).map(Pattern::Set),
+            '.' => Ok(Pattern::Set(CharSet::single('\n').negate())),
+            '\\' => self.escape().map(Pattern::Set),
+            '*' | '+' | '?' | '{' => Err(self.error_at(offset, PatternErrorKind::NothingToRepeat)),
+            char => Ok(Pattern::Set(CharSet::single(char))),
+        }
+    }
+
+    fn escape(&mut self) -> Result<CharSet, PatternError> {
+        let (_, char) = self.next()?;
+
+        Ok(match char {
+            'd' => CharSet::digits(),
+            'D' => CharSet::digits().negate(),
+            'w' => CharSet::word(),
+            'W' => CharSet::word().negate(),
+            's' => CharSet::whitespace(),
+            'S' => CharSet::whitespace().negate(),
+            'n' => CharSet::single('\n'),
+            'r' => CharSet::single('\r'),
+            't' => CharSet::single('\t'),
+            '0' => CharSet::single('\0'),
+            char => CharSet::single(char),
+        })
+    }
+
+    fn class(&mut self, start: usize) -> Result<CharSet, PatternError> {
+        let negated = self.eat('^');
+        let mut set = CharSet::new();
+        let mut first = true;
+
+        loop {
+            let (offset, char) = self
+                .chars
+                .next()
+                .ok_or_else(|| self.error_at(start, PatternErrorKind::UnclosedClass))?;
+
+            let from = match char {
+                ']' if !first => break,
+                '\\' => {
+                    let escaped = self.escape()?;
+
+                    match escaped.ranges() {
+                        [(from, to)] if from == to => *from,
+                        _ => {
+                            set = set.union(&escaped);
+                            first = false;
+                            continue;
+                        }
+                    }
+                }
+                char => char,
+            };
+
+            first = false;
+
+            if self.chars.peek().map(|&(_, char)| char) == Some('-')
+                && self
+                    .chars
+                    .clone()
+                    .nth(1)
+                    .is_some_and(|(_, char)| char != ']')
+            {
+                self.chars.next();
+
+                let to = match self.next()? {
+                    (_, '\\') => match self.escape()?.ranges() {
+                        [(from, to)] if from == to => *from,
+                        _ => return Err(self.error_at(offset, PatternErrorKind::InvalidRange)),
+                    },
+                    (_, char) => char,
+                };
+
+                if to < from {
+                    return Err(self.error_at(offset, PatternErrorKind::InvalidRange));
+                }
+
+                set.insert(from, to);
+            } else {
+                set.insert(from, from);
+            }
+        }
+
+        Ok(if negated { set.negate() } else { set })
+    }
+
+    fn repetition(&mut self, mut atom: Pattern) -> Result<Pattern, PatternError> {
+        loop {
+            let (min, max) = match self.chars.peek() {
+                Some((_, '*')) => (0, None),
+                Some((_, '+')) => (1, None),
+                Some((_, '?')) => (0, Some(1)),
+                Some(&(offset, '{')) => {
+                    self.chars.next();
+                    let bounds = self.bounds(offset)?;
+                    atom = Pattern::Repeat {
+                        pattern: Box::new(atom),
+                        min: bounds.0,
+                        max: bounds.1,
+                    };
+                    continue;
+                }
+                _ => return Ok(atom),
+            };
+
+            self.chars.next();
+
+            atom = Pattern::Repeat {
+                pattern: Box::new(atom),
+                min,
+                max,
+            };
+        }
+    }
+
+    fn bounds(&mut self, start: usize) -> Result<(usize, Option<usize>), PatternError> {
+        let min = self
+            .number()
+            .ok_or_else(|| self.error_at(start, PatternErrorKind::InvalidRepetition))?;
+
+        let max = if self.eat(',') {
+            self.number()
+        } else {
+            Some(min)
+        };
+
+        if !self.eat('}') || max.is_some_and(|max| max < min) {
+            return Err(self.error_at(start, PatternErrorKind::InvalidRepetition));
+        }
+
+        Ok((min, max))
+    }
+
+    fn number(&mut self) -> Option<usize> {
+        let mut value = None;
+
+        while let Some((_, digit)) = self.chars.next_if(|(_, char)| char.is_ascii_digit()) {
+            let digit = digit.to_digit(10)? as usize;
+            value = Some(value.unwrap_or(0) * 10 + digit);
+        }
+
+        value
+    }
+}
+
+impl Display for PatternError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Invalid pattern `{}` at offset {}: ",
+            self.pattern, self.offset
+        )?;
+
+        match self.kind {
+            PatternErrorKind::UnexpectedEnd => write!(f, "unexpected end of pattern"),
+            PatternErrorKind::UnexpectedChar(char) => write!(f, "unexpected `{char}`"),
+            PatternErrorKind::UnclosedGroup => write!(f, "unclosed group"),
+            PatternErrorKind::UnclosedClass => write!(f, "unclosed character class"),
+            PatternErrorKind::InvalidRange => write!(f, "invalid character range"),
+            PatternErrorKind::InvalidRepetition => write!(f, "invalid repetition bounds"),
+            PatternErrorKind::NothingToRepeat => write!(f, "nothing to repeat"),
+        }
+    }
+}
+
+impl std::error::Error for PatternError {}
@@ -0,0 +1,220 @@
+use std::fmt::Display;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Encoding {
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+    Latin1,
+}
+
+pub struct Decoder {
+    encoding: Option<Encoding>,
+    explicit: bool,
+    lossy: bool,
+    pending: Vec<u8>,
+}
+
+impl Encoding {
+    fn bom(self) -> &'static [u8] {
+        match self {
+            Encoding::Utf8 => b"\xEF\xBB\xBF",
+            Encoding::Utf16Le => b"\xFF\xFE",
+            Encoding::Utf16Be => b"\xFE\xFF",
+            Encoding::Latin1 => b"",
+        }
+    }
+
+    pub(crate) fn sniff(bytes: &[u8]) -> Option<Self> {
+        [Encoding::Utf8, Encoding::Utf16Le, Encoding::Utf16Be]
+            .into_iter()
+            .find(|encoding| bytes.starts_with(encoding.bom()))
+    }
+}
+
+impl Display for Encoding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Encoding::Utf8 => "UTF-8",
+            Encoding::Utf16Le => "UTF-16LE",
+            Encoding::Utf16Be => "UTF-16BE",
+            Encoding::Latin1 => "Latin-1",
+        })
+    }
+}
+
+impl Decoder {
+    pub fn new() -> Self {
+        Self {
+            encoding: None,
+            explicit: false,
+            lossy: false,
+            pending: Vec::new(),
+        }
+    }
+
+    pub fn set_encoding(&mut self, encoding: Encoding) {
+        self.encoding = Some(encoding);
+        self.explicit = true;
+    }
+
+    pub fn set_lossy(&mut self, lossy: bool) {
+        self.lossy = lossy;
+    }
+
+    pub fn encoding(&self) -> Encoding {
+        self.encoding.unwrap_or(Encoding::Utf8)
+    }
+
+    /// Decode `input` onto the end of `output`, holding back any incomplete sequence
+    /// at the end unless this is the `last` input.
+    ///
+    /// Unless decoding lossily, this stops at the first invalid sequence and returns
+    /// its bytes, with everything before it already decoded.
+    pub fn decode(&mut self, input: &[u8], last: bool, output: &mut String) -> Result<(), Vec<u8>> {
+        self.pending.extend_from_slice(input);
+
+        let bytes = std::mem::take(&mut self.pending);
+        let mut start = 0;
+
+        if self.explicit {
+            self.explicit = false;
+
+            let bom = self.encoding().bom();
+
+            if bytes.len() < bom.len() && bom.starts_with(&bytes) && !last {
+                self.explicit = true;
+                self.pending = bytes;
+                return Ok(());
+            }
+
+            if bytes.starts_with(bom) {
+                start = bom.len();
+            }
+        } else if self.encoding.is_none() {
+            if bytes.len() < 3 && !last {
+                self.pending = bytes;
+                return Ok(());
+            }
+
+            match Encoding::sniff(&bytes) {
+                Some(encoding) => {
+                    self.encoding = Some(encoding);
+                    start = encoding.bom().len();
+                }
+                None => self.encoding = Some(Encoding::Utf8),
+            }
+        }
+
+        match self.encoding() {
+            Encoding::Utf8 => self.decode_utf8(&bytes[start..], last, output),
+            Encoding::Utf16Le => {
+                self.decode_utf16(&bytes[start..], last, output, u16::from_le_bytes)
+            }
+            Encoding::Utf16Be => {
+                self.decode_utf16(&bytes[start..], last, output, u16::from_be_bytes)
+            }
+            Encoding::Latin1 => {
+                output.extend(bytes[start..].iter().map(|&byte| char::from(byte)));
+                Ok(())
+            }
+        }
+    }
+
+    fn invalid(&self, bytes: &[u8], output: &mut String) -> Result<(), Vec<u8>> {
+        if self.lossy {
+            output.push(char::REPLACEMENT_CHARACTER);
+            Ok(())
+        } else {
+            Err(bytes.to_owned())
+        }
+    }
+
+    fn decode_utf8(
+        &mut self,
+        mut bytes: &[u8],
+        last: bool,
+        output: &mut String,
+    ) -> Result<(), Vec<u8>> {
+        loop {
+            match std::str::from_utf8(bytes) {
+                Ok(text) => {
+                    output.push_str(text);
+                    return Ok(());
+                }
+                Err(error) => {
+                    let (valid, rest) = bytes.split_at(error.valid_up_to());
+                    output.push_str(std::str::from_utf8(valid).unwrap_or_default());
+
+                    match error.error_len() {
+                        Some(len) => {
+                            self.invalid(&rest[..len], output)?;
+                            bytes = &rest[len..];
+                        }
+                        None if last => return self.invalid(rest, output),
+                        None => {
+                            self.pending = rest.to_owned();
+                            return Ok(());
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    fn decode_utf16(
+        &mut self,
+        bytes: &[u8],
+        last: bool,
+        output: &mut String,
+        unit: fn([u8; 2]) -> u16,
+    ) -> Result<(), Vec<u8>> {
+        let units = bytes
+            .chunks_exact(2)
+            .map(|pair| unit([pair[0], pair[1]]))
+            .collect::<Vec<_>>();
+
+        let mut index = 0;
+
+        while index < units.len() {
+            let first = units[index];
+            let second = units.get(index + 1).copied();
+
+            match (first, second) {
+                (0xD800..=0xDBFF, Some(second @ 0xDC00..=0xDFFF)) => {
+                    let code = 0x10000
+                        + ((u32::from(first) - 0xD800) << 10)
+                        + (u32::from(second) - 0xDC00);
+                    output.extend(char::from_u32(code));
+                    index += 2;
+                    continue;
+                }
+                (0xD800..=0xDBFF, None) if !last => {
+                    self.pending = bytes[index * 2..].to_owned();
+                    return Ok(());
+                }
+                (0xD800..=0xDFFF, _) => self.invalid(&bytes[index * 2..index * 2 + 2], output)?,
+                _ => output.extend(char::from_u32(u32::from(first))),
+            }
+
+            index += 1;
+        }
+
+        let rest = &bytes[units.len() * 2..];
+
+        if rest.is_empty() {
+            Ok(())
+        } else if last {
+            self.invalid(rest, output)
+        } else {
+            self.pending = rest.to_owned();
+            Ok(())
+        }
+    }
+}
+
+impl Default for Decoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -1,17 +1,39 @@
 use std::{
     fmt::{Debug, Display},
     fs::File,
-    io::{self, BufRead, BufReader, Read, Seek},
-    path::Path,
+    io::{self, BufRead, BufReader, Cursor, Read, Seek, SeekFrom},
+    path::{Path, PathBuf},
     sync::{Arc, Mutex},
 };
 
+use crate::{
+    encoding::{Decoder, Encoding},
+    source::{SharedBuffer, SourceId, SourceMap},
+};
+
 use super::writer::{ErrorWriter, PackagedErrorWriter};
 
+const DEFAULT_TAB_STOP: usize = 4;
+
 pub struct ErrorContext<R: Read + Seek> {
-    pub(crate) source: BufReader<R>,
+    sources: Vec<Source<R>>,
+    mapped: bool,
+    tab_stop: usize,
+}
+
+struct Source<R: Read + Seek> {
+    path: Option<PathBuf>,
+    reader: BufReader<R>,
+    encoding: Option<Encoding>,
+    decoded: Option<Cursor<Vec<u8>>>,
+    line_offsets: Vec<u64>,
+    bom: u64,
 }
 
+trait Input: BufRead + Seek {}
+
+impl<T: BufRead + Seek> Input for T {}
+
 pub trait ErrorProvider: Debug {
     fn write_errors(&self, writer: &mut dyn ErrorWriter) -> std::fmt::Result;
 }
@@ -23,23 +45,221 @@ pub struct PackagedError<R: Read + Seek, E: ErrorProvider> {
 
 impl ErrorContext<File> {
     pub fn from_file<P: AsRef<Path>>(path: P) -> io::Result<Arc<Mutex<Self>>> {
+        let path = path.as_ref();
+        let source = Source::new(File::open(path)?, Some(path.to_owned()));
+
+        Ok(Arc::new(Mutex::new(Self {
+            sources: vec![source],
+            mapped: false,
+            tab_stop: DEFAULT_TAB_STOP,
+        })))
+    }
+
+    pub fn from_source_map(sources: &SourceMap) -> io::Result<Arc<Mutex<Self>>> {
+        let sources = sources
+            .iter()
+            .map(|(_, path)| Ok(Source::new(File::open(path)?, Some(path.to_owned()))))
+            .collect::<io::Result<_>>()?;
+
         Ok(Arc::new(Mutex::new(Self {
-            source: BufReader::new(File::open(path)?),
+            sources,
+            mapped: true,
+            tab_stop: DEFAULT_TAB_STOP,
         })))
     }
 }
 
+impl ErrorContext<Cursor<SharedBuffer>> {
+    pub fn from_buffer(buffer: SharedBuffer, path: Option<PathBuf>) -> Arc<Mutex<Self>> {
+        Arc::new(Mutex::new(Self {
+            sources: vec![Source::new(Cursor::new(buffer), path)],
+            mapped: false,
+            tab_stop: DEFAULT_TAB_STOP,
+        }))
+    }
+}
+
 impl<R: Read + Seek> ErrorContext<R> {
-    pub fn line(&mut self, line: usize) -> io::Result<String> {
-        self.source.rewind()?;
-        let mut buffer = String::new();
+    pub fn new(source: R) -> Self {
+        Self {
+            sources: vec![Source::new(source, None)],
+            mapped: false,
+            tab_stop: DEFAULT_TAB_STOP,
+        }
+    }
 
-        for _ in 0..=line {
+    pub fn named<P: Into<PathBuf>>(source: R, name: P) -> Self {
+        Self {
+            sources: vec![Source::new(source, Some(name.into()))],
+            mapped: false,
+            tab_stop: DEFAULT_TAB_STOP,
+        }
+    }
+
+    pub fn tab_stop(&self) -> usize {
+        self.tab_stop
+    }
+
+    pub fn set_tab_stop(&mut self, tab_stop: usize) {
+        self.tab_stop = tab_stop;
+    }
+
+    /// Read `source` as `encoding` rather than detecting its encoding from a byte
+    /// order mark, for input that was lexed with an explicit encoding
+    pub fn set_encoding(&mut self, source: SourceId, encoding: Encoding) {
+        if let Some(source) = self.source(source) {
+            source.encoding = Some(encoding);
+            source.decoded = None;
+            source.line_offsets.clear();
+        }
+    }
+
+    pub fn contains(&self, source: SourceId) -> bool {
+        self.index(source)
+            .is_some_and(|index| index < self.sources.len())
+    }
+
+    pub fn path(&self, source: SourceId) -> Option<&Path> {
+        self.sources
+            .get(self.index(source)?)
+            .and_then(|source| source.path.as_deref())
+    }
+
+    pub fn line(&mut self, source: SourceId, line: usize) -> io::Result<String> {
+        match self.source(source) {
+            Some(source) => source.line(line),
+            None => Ok(String::new()),
+        }
+    }
+
+    pub fn line_offset(&mut self, source: SourceId, line: usize) -> io::Result<Option<usize>> {
+        match self.source(source) {
+            Some(source) => Ok(source
+                .line_offset(line)?
+                .and_then(|offset| usize::try_from(offset - source.bom).ok())),
+            None => Ok(None),
+        }
+    }
+
+    fn index(&self, source: SourceId) -> Option<usize> {
+        if self.mapped {
+            source.index()
+        } else {
+            Some(0)
+        }
+    }
+
+    fn source(&mut self, source: SourceId) -> Option<&mut Source<R>> {
+        let index = self.index(source)?;
+        self.sources.get_mut(index)
+    }
+}
+
+impl<R: Read + Seek> Source<R> {
+    fn new(reader: R, path: Option<PathBuf>) -> Self {
+        Self {
+            path,
+            reader: BufReader::new(reader),
+            encoding: None,
+            decoded: None,
+            line_offsets: Vec::new(),
+            bom: 0,
+        }
+    }
+
+    fn line(&mut self, line: usize) -> io::Result<String> {
+        let mut buffer = Vec::new();
+
+        if let Some(offset) = self.line_offset(line)? {
+            let input = self.input();
+            input.seek(SeekFrom::Start(offset))?;
+            input.read_until(b'\n', &mut buffer)?;
+        }
+
+        let buffer = String::from_utf8_lossy(&buffer);
+        let end = buffer.find(['\r', '\n']).unwrap_or(buffer.len());
+        Ok(buffer[..end].trim_end().to_owned())
+    }
+
+    fn line_offset(&mut self, line: usize) -> io::Result<Option<u64>> {
+        if self.line_offsets.is_empty() {
+            let mut start = Vec::new();
+
+            self.reader.seek(SeekFrom::Start(0))?;
+            (&mut self.reader).take(3).read_to_end(&mut start)?;
+
+            let encoding = self.encoding.or_else(|| Encoding::sniff(&start));
+
+            if matches!(encoding, None | Some(Encoding::Utf8)) {
+                self.bom = if start == b"\xEF\xBB\xBF" { 3 } else { 0 };
+            } else {
+                self.decode()?;
+                self.bom = 0;
+            }
+
+            self.line_offsets.push(self.bom);
+        }
+
+        if let Some(&offset) = self.line_offsets.get(line) {
+            return Ok(Some(offset));
+        }
+
+        let mut offset = *self.line_offsets.last().unwrap_or(&0);
+        self.input().seek(SeekFrom::Start(offset))?;
+
+        let mut buffer = Vec::new();
+
+        while self.line_offsets.len() <= line {
             buffer.clear();
-            self.source.read_line(&mut buffer)?;
+
+            if self.input().read_until(b'\n', &mut buffer)? == 0 {
+                return Ok(None);
+            }
+
+            for (index, &byte) in buffer.iter().enumerate() {
+                let lone_cr = byte == b'\r' && buffer.get(index + 1) != Some(&b'\n');
+
+                if byte == b'\n' || lone_cr {
+                    self.line_offsets.push(offset + index as u64 + 1);
+                }
+            }
+
+            offset += buffer.len() as u64;
+
+            if !buffer.ends_with(b"\n") && !buffer.ends_with(b"\r") {
+                self.line_offsets.push(offset);
+            }
         }
 
-        Ok(buffer.trim_end().to_owned())
+        Ok(self.line_offsets.get(line).copied())
+    }
+
+    fn input(&mut self) -> &mut dyn Input {
+        match &mut self.decoded {
+            Some(decoded) => decoded,
+            None => &mut self.reader,
+        }
+    }
+
+    /// Decode the whole source up front, so that lines and offsets are measured in
+    /// the same UTF-8 text that was lexed
+    fn decode(&mut self) -> io::Result<()> {
+        let mut bytes = Vec::new();
+        self.reader.seek(SeekFrom::Start(0))?;
+        self.reader.read_to_end(&mut bytes)?;
+
+        let mut decoder = Decoder::new();
+        decoder.set_lossy(true);
+
+        if let Some(encoding) = self.encoding {
+            decoder.set_encoding(encoding);
+        }
+
+        let mut decoded = String::new();
+        let _ = decoder.decode(&bytes, true, &mut decoded);
+        self.decoded = Some(Cursor::new(decoded.into_bytes()));
+
+        Ok(())
     }
 }
 
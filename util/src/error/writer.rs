@@ -3,7 +3,7 @@ use std::{
     io::{Read, Seek},
 };
 
-use crate::span::Span;
+use crate::span::{Columns, Span};
 
 use super::context::ErrorContext;
 
@@ -28,20 +28,63 @@ impl<'ctx, 'fmt, 'a, R: Read + Seek> PackagedErrorWriter<'ctx, 'fmt, 'a, R> {
     }
 
     fn write_span(&mut self, span: Span<()>) -> std::fmt::Result {
-        if span.start.line == span.end.line {
-            write!(
-                self.fmt,
-                "\n\t> {}\n\t> ",
-                self.context
-                    .line(span.start.line)
-                    .map_err(|_| std::fmt::Error)?,
-            )?;
-
-            for _ in 0..span.start.col {
+        if span.start.line == span.end.line && self.context.contains(span.source()) {
+            let text = self
+                .context
+                .line(span.source(), span.start.line)
+                .map_err(|_| std::fmt::Error)?;
+
+            let line_offset = self
+                .context
+                .line_offset(span.source(), span.start.line)
+                .map_err(|_| std::fmt::Error)?;
+
+            let columns = Columns::Display {
+                tab_stop: self.context.tab_stop(),
+            };
+
+            write!(self.fmt, "\n\t> ")?;
+
+            let mut col = 0;
+
+            for (index, char) in text.char_indices() {
+                let width = columns.width(&text, index, col);
+
+                if char == '\t' {
+                    for _ in 0..width {
+                        self.fmt.write_char(' ')?;
+                    }
+                } else {
+                    self.fmt.write_char(char)?;
+                }
+
+                col += width;
+            }
+
+            write!(self.fmt, "\n\t> ")?;
+
+            let index = |offset: usize| {
+                let index = offset.checked_sub(line_offset?)?.min(text.len());
+                text.is_char_boundary(index).then_some(index)
+            };
+
+            let (start, width) = match (index(span.start.offset), index(span.end.offset)) {
+                (Some(start), Some(end)) if start <= end => {
+                    let prefix = columns.measure(&text[..start], 0);
+                    let width = columns.measure(&text[start..end], prefix);
+                    (
+                        prefix,
+                        width.max(usize::from(span.end.col > span.start.col)),
+                    )
+                }
+                _ => (span.start.col, span.end.col.saturating_sub(span.start.col)),
+            };
+
+            for _ in 0..start {
                 self.fmt.write_char(' ')?;
             }
 
-            for _ in span.start.col..span.end.col {
+            for _ in 0..width {
                 self.fmt.write_char('^')?;
             }
         }
@@ -49,16 +92,22 @@ impl<'ctx, 'fmt, 'a, R: Read + Seek> PackagedErrorWriter<'ctx, 'fmt, 'a, R> {
         Ok(())
     }
 
+    fn write_location(&mut self, span: Span<()>) -> std::fmt::Result {
+        if let Some(path) = self.context.path(span.source()) {
+            write!(self.fmt, "{}:", path.display())?;
+        }
+
+        write!(self.fmt, "{}:{}", span.start.line, span.start.col)
+    }
+
     fn unspanned_error(&mut self, message: &str) -> std::fmt::Result {
         writeln!(self.fmt, "error: {}", message)
     }
 
     fn spanned_error(&mut self, span: Span<()>, message: &str) -> std::fmt::Result {
-        writeln!(
-            self.fmt,
-            "error: {}:{}: {}",
-            span.start.line, span.start.col, message
-        )?;
+        write!(self.fmt, "error: ")?;
+        self.write_location(span)?;
+        writeln!(self.fmt, ": {}", message)?;
         self.write_span(span)
     }
 
@@ -85,11 +134,119 @@ impl<'ctx, 'fmt, 'a, R: Read + Seek> ErrorWriter for PackagedErrorWriter<'ctx, '
     fn note(&mut self, span: Span<()>, message: &str) -> std::fmt::Result {
         self.write_newlines()?;
 
-        writeln!(
-            self.fmt,
-            "note: {}:{}: {}",
-            span.start.line, span.start.col, message
-        )?;
+        write!(self.fmt, "note: ")?;
+        self.write_location(span)?;
+        writeln!(self.fmt, ": {}", message)?;
         self.write_span(span)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use std::{
+        fmt::Display,
+        io::Cursor,
+        sync::{Arc, Mutex},
+    };
+
+    use crate::{
+        error::context::{ErrorContext, ErrorProvider, PackagedError},
+        source::{SourceId, SourceMap},
+        span::Location,
+    };
+
+    use super::*;
+
+    #[derive(Debug)]
+    struct Marker(Span<()>);
+
+    impl ErrorProvider for Marker {
+        fn write_errors(&self, writer: &mut dyn ErrorWriter) -> std::fmt::Result {
+            writer.error(Some(self.0), "here")
+        }
+    }
+
+    fn render(input: &'static str, text: &str) -> impl Display {
+        let start = input.find(text).unwrap();
+        let context = Arc::new(Mutex::new(ErrorContext::new(Cursor::new(input))));
+
+        PackagedError::new(
+            context,
+            Marker(Span::new(
+                Location::new(0, 0, start),
+                Location::new(0, text.chars().count(), start + text.len()),
+                (),
+            )),
+        )
+    }
+
+    #[test]
+    fn carets_align_with_tabs_and_wide_chars() {
+        assert_eq!(
+            render("\tx = 1", "x").to_string(),
+            "error: 0:0: here\n\n\t>     x = 1\n\t>     ^"
+        );
+
+        assert_eq!(
+            render("漢字 + e\u{301} + oops", "oops").to_string(),
+            "error: 0:0: here\n\n\t> 漢字 + e\u{301} + oops\n\t>            ^^^^"
+        );
+
+        assert_eq!(
+            render("👨\u{200d}👩\u{200d}👧 = fam", "fam").to_string(),
+            "error: 0:0: here\n\n\t> 👨\u{200d}👩\u{200d}👧 = fam\n\t>      ^^^"
+        );
+    }
+
+    #[test]
+    fn locations_include_source_paths() {
+        let context = Arc::new(Mutex::new(ErrorContext::named(
+            Cursor::new("let x = 1"),
+            "main.rs",
+        )));
+
+        let span = Span::new(Location::new(0, 4, 4), Location::new(0, 5, 5), ());
+
+        assert_eq!(
+            PackagedError::new(context, Marker(span)).to_string(),
+            "error: main.rs:0:4: here\n\n\t> let x = 1\n\t>     ^"
+        );
+    }
+
+    #[test]
+    fn unregistered_sources_are_not_rendered_against_a_source_map() {
+        let directory = std::env::temp_dir();
+        let first = directory.join(format!("ruinous-first-{}.txt", std::process::id()));
+        let second = directory.join(format!("ruinous-second-{}.txt", std::process::id()));
+        std::fs::write(&first, "first").unwrap();
+        std::fs::write(&second, "second").unwrap();
+
+        let mut sources = SourceMap::new();
+        sources.add(&first);
+        let source = sources.add(&second);
+
+        let context = ErrorContext::from_source_map(&sources).unwrap();
+        let span = |source| {
+            Span::new(
+                Location::new(0, 0, 0).in_source(source),
+                Location::new(0, 1, 1).in_source(source),
+                (),
+            )
+        };
+
+        let registered = PackagedError::new(context.clone(), Marker(span(source)));
+        let unregistered = PackagedError::new(context, Marker(span(SourceId::default())));
+
+        let rendered = (registered.to_string(), unregistered.to_string());
+        std::fs::remove_file(&first).unwrap();
+        std::fs::remove_file(&second).unwrap();
+
+        assert_eq!(
+            rendered,
+            (
+                format!("error: {}:0:0: here\n\n\t> second\n\t> ^", second.display()),
+                "error: 0:0: here\n".to_owned()
+            )
+        );
+    }
+}
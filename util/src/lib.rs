@@ -1,2 +1,5 @@
+pub mod automaton;
+pub mod encoding;
 pub mod error;
+pub mod source;
 pub mod span;
@@ -0,0 +1,171 @@
+use std::{
+    fmt::Debug,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+/// The file a span came from, or the default for input that was never registered in a
+/// [`SourceMap`].
+#[derive(Debug, Copy, Clone, PartialOrd, Ord, PartialEq, Eq, Hash)]
+pub struct SourceId(usize);
+
+#[derive(Default, Debug)]
+pub struct SourceMap {
+    paths: Vec<PathBuf>,
+}
+
+#[derive(Clone)]
+pub struct SharedBuffer {
+    storage: Storage,
+}
+
+#[derive(Clone)]
+enum Storage {
+    Text(Arc<dyn AsRef<str> + Send + Sync>),
+    Bytes(Arc<dyn AsRef<[u8]> + Send + Sync>),
+    #[cfg(feature = "mmap")]
+    Map {
+        map: Arc<memmap2::Mmap>,
+        utf8: bool,
+    },
+}
+
+impl SourceId {
+    const UNREGISTERED: Self = Self(usize::MAX);
+
+    pub fn index(self) -> Option<usize> {
+        (self != Self::UNREGISTERED).then_some(self.0)
+    }
+}
+
+impl Default for SourceId {
+    fn default() -> Self {
+        Self::UNREGISTERED
+    }
+}
+
+impl SourceMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add<P: AsRef<Path>>(&mut self, path: P) -> SourceId {
+        self.paths.push(path.as_ref().to_owned());
+        SourceId(self.paths.len() - 1)
+    }
+
+    pub fn path(&self, source: SourceId) -> Option<&Path> {
+        self.paths.get(source.index()?).map(PathBuf::as_path)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (SourceId, &Path)> {
+        self.paths
+            .iter()
+            .enumerate()
+            .map(|(index, path)| (SourceId(index), path.as_path()))
+    }
+}
+
+impl SharedBuffer {
+    pub fn new<B: AsRef<[u8]> + Send + Sync + 'static>(bytes: B) -> Self {
+        Self {
+            storage: Storage::Bytes(Arc::new(bytes)),
+        }
+    }
+
+    fn text<T: AsRef<str> + Send + Sync + 'static>(text: T) -> Self {
+        Self {
+            storage: Storage::Text(Arc::new(text)),
+        }
+    }
+
+    #[cfg(feature = "mmap")]
+    pub fn map<P: AsRef<Path>>(path: P) -> std::io::Result<Self> {
+        let file = std::fs::File::open(path)?;
+
+        // SAFETY: the map is read-only, and like any memory map it is only sound as long
+        // as the file is not modified while the buffer is alive
+        let map = unsafe { memmap2::Mmap::map(&file)? };
+        let utf8 = std::str::from_utf8(&map).is_ok();
+
+        Ok(Self {
+            storage: Storage::Map {
+                map: Arc::new(map),
+                utf8,
+            },
+        })
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        match &self.storage {
+            Storage::Text(text) => (**text).as_ref().as_bytes(),
+            Storage::Bytes(bytes) => (**bytes).as_ref(),
+            #[cfg(feature = "mmap")]
+            Storage::Map { map, .. } => map,
+        }
+    }
+
+    /// The buffer as text, if it is known to be valid UTF-8 and so can be borrowed
+    /// without being decoded.
+    pub fn as_str(&self) -> Option<&str> {
+        match &self.storage {
+            Storage::Text(text) => Some((**text).as_ref()),
+            Storage::Bytes(_) => None,
+            // SAFETY: the map was checked to be UTF-8 when it was created, and is only
+            // sound to use at all while the file is not modified
+            #[cfg(feature = "mmap")]
+            Storage::Map { map, utf8 } => {
+                utf8.then(|| unsafe { std::str::from_utf8_unchecked(map) })
+            }
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.as_bytes().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.as_bytes().is_empty()
+    }
+}
+
+impl AsRef<[u8]> for SharedBuffer {
+    fn as_ref(&self) -> &[u8] {
+        self.as_bytes()
+    }
+}
+
+impl Debug for SharedBuffer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SharedBuffer")
+            .field("len", &self.len())
+            .finish()
+    }
+}
+
+impl From<String> for SharedBuffer {
+    fn from(text: String) -> Self {
+        Self::text(text)
+    }
+}
+
+impl From<Arc<str>> for SharedBuffer {
+    fn from(text: Arc<str>) -> Self {
+        Self::text(text)
+    }
+}
+
+impl From<Vec<u8>> for SharedBuffer {
+    fn from(bytes: Vec<u8>) -> Self {
+        match String::from_utf8(bytes) {
+            Ok(text) => Self::text(text),
+            Err(err) => Self::new(err.into_bytes()),
+        }
+    }
+}
+
+impl From<&'static str> for SharedBuffer {
+    fn from(text: &'static str) -> Self {
+        Self::text(text)
+    }
+}
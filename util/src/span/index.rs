@@ -0,0 +1,354 @@
+use crate::source::SourceId;
+
+use super::{Columns, Location, Span};
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PositionEncoding {
+    Utf8,
+    #[default]
+    Utf16,
+    Utf32,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Position {
+    pub line: usize,
+    pub character: usize,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PositionRange {
+    pub start: Position,
+    pub end: Position,
+}
+
+#[derive(Debug, Clone)]
+pub struct LineIndex {
+    source: SourceId,
+    lines: Vec<IndexedLine>,
+}
+
+#[derive(Debug, Clone)]
+struct IndexedLine {
+    start: usize,
+    end: usize,
+    wide: Vec<WideChar>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct WideChar {
+    offset: usize,
+    len: usize,
+    /// The display width, or `None` for a tab whose width depends on its column
+    width: Option<usize>,
+}
+
+impl PositionEncoding {
+    fn units(self, len_utf8: usize) -> usize {
+        match self {
+            PositionEncoding::Utf8 => len_utf8,
+            PositionEncoding::Utf16 => len_utf8 / 4 + 1,
+            PositionEncoding::Utf32 => 1,
+        }
+    }
+}
+
+impl Position {
+    pub fn new(line: usize, character: usize) -> Self {
+        Self { line, character }
+    }
+}
+
+impl PositionRange {
+    pub fn new(start: Position, end: Position) -> Self {
+        Self { start, end }
+    }
+}
+
+impl LineIndex {
+    pub fn new(text: &str) -> Self {
+        let mut lines = Vec::new();
+        let mut line = IndexedLine {
+            start: 0,
+            end: 0,
+            wide: Vec::new(),
+        };
+
+        let mut chars = text.char_indices().peekable();
+
+        while let Some((offset, char)) = chars.next() {
+            let next = match char {
+                '\r' if chars.peek().is_some_and(|&(_, next)| next == '\n') => {
+                    chars.next();
+                    offset + 2
+                }
+                '\r' | '\n' => offset + 1,
+                '\t' => {
+                    line.wide.push(WideChar {
+                        offset: offset - line.start,
+                        len: 1,
+                        width: None,
+                    });
+
+                    continue;
+                }
+                _ => {
+                    if !char.is_ascii() {
+                        let rest = &text[line.start..];
+                        let offset = offset - line.start;
+
+                        line.wide.push(WideChar {
+                            offset,
+                            len: char.len_utf8(),
+                            width: Some(Columns::Display { tab_stop: 1 }.width(rest, offset, 0)),
+                        });
+                    }
+
+                    continue;
+                }
+            };
+
+            line.end = offset;
+            lines.push(std::mem::replace(
+                &mut line,
+                IndexedLine {
+                    start: next,
+                    end: next,
+                    wide: Vec::new(),
+                },
+            ));
+        }
+
+        line.end = text.len();
+        lines.push(line);
+
+        Self {
+            source: SourceId::default(),
+            lines,
+        }
+    }
+
+    #[must_use]
+    pub fn in_source(self, source: SourceId) -> Self {
+        Self { source, ..self }
+    }
+
+    pub fn lines(&self) -> usize {
+        self.lines.len()
+    }
+
+    pub fn len(&self) -> usize {
+        self.lines.last().map_or(0, |line| line.end)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The position of the byte `offset`, which is clamped to the end of its line
+    pub fn position(&self, offset: usize, encoding: PositionEncoding) -> Option<Position> {
+        if offset > self.len() {
+            return None;
+        }
+
+        let index = self.lines.partition_point(|line| line.start <= offset) - 1;
+        let line = &self.lines[index];
+        let column = offset.min(line.end) - line.start;
+
+        let character = line
+            .wide
+            .iter()
+            .take_while(|wide| wide.offset < column)
+            .fold(column, |character, wide| {
+                character - wide.len + encoding.units(wide.len)
+            });
+
+        Some(Position::new(index, character))
+    }
+
+    /// The byte offset of `position`, clamped to the end of its line and rounded down
+    /// to the start of a character
+    pub fn offset(&self, position: Position, encoding: PositionEncoding) -> Option<usize> {
+        let line = self.lines.get(position.line)?;
+
+        let mut remaining = position.character;
+        let mut column = 0;
+
+        for wide in &line.wide {
+            let gap = wide.offset - column;
+
+            if remaining <= gap {
+                return Some(line.start + column + remaining);
+            }
+
+            remaining -= gap;
+            column = wide.offset;
+
+            let units = encoding.units(wide.len);
+
+            if remaining < units {
+                return Some(line.start + column);
+            }
+
+            remaining -= units;
+            column += wide.len;
+        }
+
+        Some((line.start + column + remaining).min(line.end))
+    }
+
+    /// The location of the byte `offset`, with columns counted as `columns` would count
+    /// them while reading
+    pub fn location(&self, offset: usize, columns: Columns) -> Option<Location> {
+        let encoding = match columns {
+            Columns::Chars => PositionEncoding::Utf32,
+            Columns::Bytes => PositionEncoding::Utf8,
+            Columns::Utf16 => PositionEncoding::Utf16,
+            Columns::Display { tab_stop } => {
+                let position = self.position(offset, PositionEncoding::Utf8)?;
+                let col = self.display_col(position, tab_stop);
+
+                return Some(Location::new(position.line, col, offset).in_source(self.source));
+            }
+        };
+
+        let position = self.position(offset, encoding)?;
+        Some(Location::new(position.line, position.character, offset).in_source(self.source))
+    }
+
+    pub fn range<Data>(
+        &self,
+        span: &Span<Data>,
+        encoding: PositionEncoding,
+    ) -> Option<PositionRange> {
+        Some(PositionRange::new(
+            self.position(span.start.offset, encoding)?,
+            self.position(span.end.offset, encoding)?,
+        ))
+    }
+
+    pub fn span(
+        &self,
+        range: PositionRange,
+        encoding: PositionEncoding,
+        columns: Columns,
+    ) -> Option<Span<()>> {
+        Some(Span::new(
+            self.location(self.offset(range.start, encoding)?, columns)?,
+            self.location(self.offset(range.end, encoding)?, columns)?,
+            (),
+        ))
+    }
+
+    fn display_col(&self, position: Position, tab_stop: usize) -> usize {
+        let line = &self.lines[position.line];
+        let tab_stop = tab_stop.max(1);
+
+        let (col, end) = line
+            .wide
+            .iter()
+            .take_while(|wide| wide.offset < position.character)
+            .fold((0, 0), |(col, end), wide| {
+                let col = col + wide.offset - end;
+                let width = wide.width.unwrap_or(tab_stop - col % tab_stop);
+
+                (col + width, wide.offset + wide.len)
+            });
+
+        col + position.character - end
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const TEXT: &str = "let a = \"héllo\";\r\n😀 = 1\rend\n";
+
+    #[test]
+    fn index_positions() {
+        let index = LineIndex::new(TEXT);
+        let smiley = TEXT.find('😀').unwrap();
+        let one = TEXT.find('1').unwrap();
+        let quote = TEXT.rfind('"').unwrap();
+
+        assert_eq!(index.lines(), 4);
+
+        for (encoding, character) in [
+            (PositionEncoding::Utf8, 15),
+            (PositionEncoding::Utf16, 14),
+            (PositionEncoding::Utf32, 14),
+        ] {
+            let position = Position::new(0, character);
+            assert_eq!(index.position(quote, encoding), Some(position));
+            assert_eq!(index.offset(position, encoding), Some(quote));
+        }
+
+        assert_eq!(
+            index.position(one, PositionEncoding::Utf16),
+            Some(Position::new(1, 5))
+        );
+        assert_eq!(
+            index.position(one, PositionEncoding::Utf32),
+            Some(Position::new(1, 4))
+        );
+        assert_eq!(
+            index.offset(Position::new(1, 1), PositionEncoding::Utf16),
+            Some(smiley)
+        );
+        assert_eq!(
+            index.offset(Position::new(2, 10), PositionEncoding::Utf16),
+            Some(TEXT.len() - 1)
+        );
+        assert_eq!(
+            index.position(TEXT.len(), PositionEncoding::Utf16),
+            Some(Position::new(3, 0))
+        );
+        assert_eq!(
+            index.offset(Position::new(4, 0), PositionEncoding::Utf16),
+            None
+        );
+    }
+
+    #[test]
+    fn index_ranges() {
+        let source = SourceId::default();
+        let index = LineIndex::new(TEXT).in_source(source);
+        let start = TEXT.find('😀').unwrap();
+
+        let span = Span::new(
+            Location::new(1, 0, start),
+            Location::new(1, 1, start + 4),
+            (),
+        );
+
+        let range = index.range(&span, PositionEncoding::Utf16).unwrap();
+        assert_eq!(
+            range,
+            PositionRange::new(Position::new(1, 0), Position::new(1, 2))
+        );
+        assert_eq!(
+            index.span(range, PositionEncoding::Utf16, Columns::Chars),
+            Some(span)
+        );
+    }
+
+    #[test]
+    fn index_locations() {
+        let text = "\ta漢e\u{301}👨\u{200d}👩b\n😀 = 1";
+        let index = LineIndex::new(text);
+        let cols = |offset| {
+            [
+                Columns::Chars,
+                Columns::Bytes,
+                Columns::Utf16,
+                Columns::Display { tab_stop: 4 },
+            ]
+            .map(|columns| index.location(offset, columns).unwrap().col)
+        };
+
+        assert_eq!(cols(text.find('b').unwrap()), [8, 19, 10, 10]);
+        assert_eq!(cols(text.find('1').unwrap()), [4, 7, 5, 5]);
+        assert_eq!(cols(text.len()), [5, 8, 6, 6]);
+    }
+}
@@ -0,0 +1,137 @@
+use std::ops::Range;
+
+use unicode_segmentation::{GraphemeCursor, UnicodeSegmentation};
+use unicode_width::UnicodeWidthStr;
+
+use crate::source::SourceId;
+
+pub use self::index::{LineIndex, Position, PositionEncoding, PositionRange};
+
+mod index;
+
+#[derive(Default, Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Columns {
+    #[default]
+    Chars,
+    Bytes,
+    Utf16,
+    Display {
+        tab_stop: usize,
+    },
+}
+
+#[derive(Default, Debug, Copy, Clone, PartialOrd, Ord, PartialEq, Eq)]
+pub struct Location {
+    pub source: SourceId,
+    pub line: usize,
+    pub col: usize,
+    /// The byte offset into the decoded UTF-8 text, which leaves out any byte order mark,
+    /// so it only indexes the original bytes of UTF-8 input that has none.
+    pub offset: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialOrd, Ord, PartialEq, Eq)]
+pub struct Span<Data> {
+    pub start: Location,
+    pub end: Location,
+    pub data: Data,
+}
+
+impl Location {
+    pub fn new(line: usize, col: usize, offset: usize) -> Self {
+        Self {
+            source: SourceId::default(),
+            line,
+            col,
+            offset,
+        }
+    }
+
+    #[must_use]
+    pub fn in_source(self, source: SourceId) -> Self {
+        Self { source, ..self }
+    }
+
+    pub fn next_line(&mut self) {
+        self.line += 1;
+        self.col = 0;
+    }
+
+    /// Advance past a single character which took up `bytes` bytes in the input
+    pub fn advance(&mut self, bytes: usize) {
+        self.advance_by(bytes, 1);
+    }
+
+    pub fn advance_by(&mut self, bytes: usize, cols: usize) {
+        self.col += cols;
+        self.offset += bytes;
+    }
+}
+
+impl Columns {
+    /// The number of columns taken up by the character at byte `index` of `line`,
+    /// when it appears at column `col`
+    pub fn width(self, line: &str, index: usize, col: usize) -> usize {
+        let Some(char) = line[index..].chars().next() else {
+            return 0;
+        };
+
+        match self {
+            Columns::Chars => 1,
+            Columns::Bytes => char.len_utf8(),
+            Columns::Utf16 => char.len_utf16(),
+            Columns::Display { tab_stop } => match char {
+                '\t' => {
+                    let tab_stop = tab_stop.max(1);
+                    tab_stop - col % tab_stop
+                }
+                _ if char.is_control() => 1,
+                _ if !GraphemeCursor::new(index, line.len(), true)
+                    .is_boundary(line, 0)
+                    .unwrap_or(true) =>
+                {
+                    0
+                }
+                _ => line[index..].graphemes(true).next().map_or(0, str::width),
+            },
+        }
+    }
+
+    /// The number of columns taken up by `text` when it starts at column `col`
+    pub fn measure(self, text: &str, col: usize) -> usize {
+        text.char_indices()
+            .fold(col, |col, (index, _)| col + self.width(text, index, col))
+            - col
+    }
+}
+
+impl<Data> Span<Data> {
+    pub fn new(start: Location, end: Location, data: Data) -> Self {
+        Self { start, end, data }
+    }
+
+    pub fn swap<New>(&self, new: New) -> Span<New> {
+        Span {
+            start: self.start,
+            end: self.end,
+            data: new,
+        }
+    }
+
+    pub fn map<New, F: FnOnce(Data) -> New>(self, f: F) -> Span<New> {
+        Span {
+            start: self.start,
+            end: self.end,
+            data: f(self.data),
+        }
+    }
+
+    pub fn source(&self) -> SourceId {
+        self.start.source
+    }
+
+    /// The byte range of the input covered by this span
+    pub fn range(&self) -> Range<usize> {
+        self.start.offset..self.end.offset
+    }
+}